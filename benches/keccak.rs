@@ -0,0 +1,154 @@
+//! criterion benchmarks of the permutations, sponges and generators. The
+//! benchmark ids don't change between commits, so a saved baseline can be
+//! compared against later runs:
+//!
+//!     cargo bench -- --save-baseline before
+//!     git checkout other-commit
+//!     cargo bench -- --baseline before
+//!
+//! A filter picks out one group, eg. `cargo bench -- permutation/`.
+
+use criterion::{
+    criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, BenchmarkId, Criterion,
+    Throughput,
+};
+use rand_keccak::{
+    k12, keccak_f::Lane, sha3_256, sha3_512, shake128, shake256, thread_rng, BitStream, Keccak,
+    KeccakCtr, KeccakF,
+};
+use std::hint::black_box;
+
+/// a permuted state, so the steps don't run on zeros
+fn state<L: Lane>() -> KeccakF<L> {
+    let mut temp = KeccakF::<L>::new();
+    temp.permute();
+    return temp;
+}
+
+fn steps_for<L: Lane>(group: &mut BenchmarkGroup<WallTime>) {
+    let width = 25 * L::BITS;
+    let mut k = state::<L>();
+    group.bench_function(BenchmarkId::new("theta", width), |b| {
+        b.iter(|| black_box(&mut k).theta())
+    });
+    group.bench_function(BenchmarkId::new("rho", width), |b| {
+        b.iter(|| black_box(&mut k).rho())
+    });
+    group.bench_function(BenchmarkId::new("pi", width), |b| {
+        b.iter(|| black_box(&mut k).pi())
+    });
+    group.bench_function(BenchmarkId::new("chi", width), |b| {
+        b.iter(|| black_box(&mut k).chi())
+    });
+    group.bench_function(BenchmarkId::new("iota", width), |b| {
+        b.iter(|| black_box(&mut k).iota(black_box(0)))
+    });
+    group.bench_function(BenchmarkId::new("round", width), |b| {
+        b.iter(|| black_box(&mut k).round(black_box(0)))
+    });
+}
+
+/// each step mapping of the lane based KeccakF on its own
+fn steps(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step");
+    steps_for::<u8>(&mut group);
+    steps_for::<u16>(&mut group);
+    steps_for::<u32>(&mut group);
+    steps_for::<u64>(&mut group);
+    group.finish();
+}
+
+fn permutations_for<L: Lane>(group: &mut BenchmarkGroup<WallTime>) {
+    let width = 25 * L::BITS;
+    let mut k = state::<L>();
+    for rounds in [1, 12, KeccakF::<L>::ROUNDS] {
+        let id = BenchmarkId::new(format!("KeccakF-{}", width), rounds);
+        group.bench_function(id, |b| {
+            b.iter(|| black_box(&mut k).permute_rounds(black_box(rounds)))
+        });
+    }
+}
+
+/// Keccak-p per width and round count, for KeccakF and the bit level Keccak
+fn permutations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("permutation");
+    permutations_for::<u8>(&mut group);
+    permutations_for::<u16>(&mut group);
+    permutations_for::<u32>(&mut group);
+    permutations_for::<u64>(&mut group);
+    for depth in [1_usize, 2, 4, 8, 16, 32, 64] {
+        let mut k = Keccak::new_sized(&BitStream::from_bytes(b"bench"), depth);
+        let full = 12 + 2 * depth.trailing_zeros() as u64;
+        for rounds in [1, full] {
+            let id = BenchmarkId::new(format!("Keccak-{}", 25 * depth), rounds);
+            group.bench_function(id, |b| {
+                b.iter(|| black_box(&mut k).keccak(black_box(rounds)))
+            });
+        }
+    }
+    group.finish();
+}
+
+/// hash and XOF throughput by input length, with 32 bytes of output from
+/// the XOFs
+fn sponges(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sponge");
+    for len in [64, 1024, 64 * 1024] {
+        let input = vec![0x5e_u8; len];
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("sha3-256", len), &input, |b, input| {
+            b.iter(|| sha3_256(black_box(input)))
+        });
+        group.bench_with_input(BenchmarkId::new("sha3-512", len), &input, |b, input| {
+            b.iter(|| sha3_512(black_box(input)))
+        });
+        let mut out = [0; 32];
+        group.bench_with_input(BenchmarkId::new("shake128", len), &input, |b, input| {
+            b.iter(|| shake128(black_box(input), &mut out))
+        });
+        group.bench_with_input(BenchmarkId::new("shake256", len), &input, |b, input| {
+            b.iter(|| shake256(black_box(input), &mut out))
+        });
+        group.bench_with_input(BenchmarkId::new("k12", len), &input, |b, input| {
+            b.iter(|| k12(black_box(input), &[], &mut out))
+        });
+    }
+    group.finish();
+}
+
+/// output rates of the generators, filling 4 KiB at a time
+fn generators(c: &mut Criterion) {
+    const WORDS: usize = 512;
+    let mut group = c.benchmark_group("generator");
+    group.throughput(Throughput::Bytes(8 * WORDS as u64));
+    let seed = BitStream::from_bytes(b"bench");
+    let mut words = [0_u64; WORDS];
+    for depth in [8_usize, 16, 32, 64] {
+        let rounds = 12 + 2 * depth.trailing_zeros() as u64;
+        let mut ctr = KeccakCtr::new(&seed, depth, rounds);
+        group.bench_function(BenchmarkId::new("KeccakCtr", 25 * depth), |b| {
+            b.iter(|| ctr.fill(black_box(&mut words)))
+        });
+    }
+    // the first lane of a bit level state permuted in place, what
+    // the removed perf binary used to time
+    let mut k = Keccak::new_sized(&seed, 8);
+    group.bench_function(BenchmarkId::new("sequential", 200), |b| {
+        b.iter(|| {
+            for word in words.iter_mut() {
+                *word = k.copy_to_u64();
+                k.keccak(18);
+            }
+            black_box(&words);
+        })
+    });
+    let mut rng = thread_rng();
+    let mut bytes = [0_u8; 8 * WORDS];
+    group.bench_function("thread_rng", |b| {
+        b.iter(|| rng.fill_bytes(black_box(&mut bytes)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, steps, permutations, sponges, generators);
+criterion_main!(benches);
@@ -0,0 +1,206 @@
+//! writes raw generator output to stdout for test batteries that read from
+//! stdin, eg. PractRand, TestU01 or dieharder:
+//!
+//!     cargo run --release --bin keccak_stream -- --seed 5eed | RNG_test stdin64
+//!
+//! Output is endless unless --bytes is given. Without --seed a random seed is
+//! drawn and printed to stderr, so the run can be repeated.
+
+use rand_keccak::{keccak_f::Lane, shake256, BitStream, Error, Keccak, KeccakCtr, KeccakF, Sponge};
+use std::{
+    env,
+    io::{self, Write},
+    process,
+};
+
+const USAGE: &str = "\
+usage: keccak_stream [--width BITS] [--rounds N] [--seed HEX] [--bytes N]
+                     [--extract ctr|sequential|sponge]
+
+  --width    permutation width, 200, 400, 800 or 1600 bits, default 1600
+  --rounds   rounds of each permutation, at least 1, default the full
+             permutation
+  --seed     seed bytes as hex, any length, default a random seed printed
+             to stderr. ctr and sequential start from SHAKE256 of the seed,
+             so seeds like 00 and 0000 give different streams
+  --bytes    stop after N bytes, default endless
+  --extract  how output is taken from the permutation, default ctr:
+               ctr         KeccakCtr, the first half of the seeded state
+                           permuted with a block counter xor'ed in
+               sequential  the first 64 bits of the seeded state after each
+                           of repeated permutations in place
+               sponge      squeezed SHAKE style after absorbing the seed,
+                           with half the state as capacity
+
+u64 outputs are written big-endian.";
+
+enum Extract {
+    Ctr,
+    Sequential,
+    Sponge,
+}
+
+struct Options {
+    width: usize,
+    rounds: Option<u64>,
+    seed: Option<Vec<u8>>,
+    bytes: Option<u64>,
+    extract: Extract,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        width: 1600,
+        rounds: None,
+        seed: None,
+        bytes: None,
+        extract: Extract::Ctr,
+    };
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            println!("{}", USAGE);
+            process::exit(0);
+        }
+        let val = match args.next() {
+            Some(val) => val,
+            None => return Err(format!("{} needs a value", arg)),
+        };
+        let number = || -> Result<u64, String> {
+            return val.parse().map_err(|_| format!("{} isn't a number", val));
+        };
+        match arg.as_str() {
+            "--width" => options.width = number()? as usize,
+            "--rounds" => match number()? {
+                0 => return Err("--rounds must be at least 1".to_string()),
+                rounds => options.rounds = Some(rounds),
+            },
+            "--bytes" => options.bytes = Some(number()?),
+            "--seed" => match BitStream::from_hex(&val) {
+                Ok(bits) if bits.len().is_multiple_of(8) => options.seed = Some(bits.to_bytes()),
+                _ => return Err(format!("{} isn't a whole number of hex bytes", val)),
+            },
+            "--extract" => {
+                options.extract = match val.as_str() {
+                    "ctr" => Extract::Ctr,
+                    "sequential" => Extract::Sequential,
+                    "sponge" => Extract::Sponge,
+                    _ => return Err(format!("unknown extraction {}", val)),
+                }
+            }
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+    return Ok(options);
+}
+
+/// fills its argument with the next output, a multiple of 8 bytes long
+type Source = Box<dyn FnMut(&mut [u8])>;
+
+fn fill_u64(dest: &mut [u8], mut next: impl FnMut() -> u64) {
+    for chunk in dest.chunks_mut(8) {
+        chunk.copy_from_slice(&next().to_be_bytes());
+    }
+}
+
+fn sponge<L: Lane + 'static>(seed: &[u8], rounds: u64) -> Result<Source, Error> {
+    let mut sponge = Sponge::<L>::try_with_rounds(KeccakF::<L>::BYTES / 2, rounds as usize)?;
+    sponge.absorb(seed);
+    sponge.finish(0x1F);
+    return Ok(Box::new(move |dest: &mut [u8]| sponge.squeeze(dest)));
+}
+
+/// the initial state for ctr and sequential. Copying the seed in directly
+/// would give seeds that differ only in trailing zero bytes, or only past
+/// the width, the same state.
+fn key(seed: &[u8], depth: usize) -> BitStream {
+    let mut temp = vec![0; 25 * depth / 8];
+    shake256(seed, &mut temp);
+    return BitStream::from_bytes(&temp);
+}
+
+fn source(options: &Options, seed: &[u8]) -> Result<Source, Error> {
+    let depth = match options.width {
+        200 | 400 | 800 | 1600 => options.width / 25,
+        width => return Err(Error::InvalidWidth { width }),
+    };
+    let rounds = options
+        .rounds
+        .unwrap_or(12 + 2 * depth.trailing_zeros() as u64);
+    return match options.extract {
+        Extract::Ctr => {
+            let mut ctr = KeccakCtr::try_new(&key(seed, depth), depth, rounds)?;
+            Ok(Box::new(move |dest: &mut [u8]| {
+                fill_u64(dest, || ctr.next_u64())
+            }))
+        }
+        Extract::Sequential => {
+            let mut k = Keccak::new_sized(&key(seed, depth), depth);
+            k.try_keccak(rounds)?;
+            Ok(Box::new(move |dest: &mut [u8]| {
+                fill_u64(dest, || {
+                    let val = k.copy_to_u64();
+                    k.keccak(rounds);
+                    return val;
+                })
+            }))
+        }
+        Extract::Sponge => match depth {
+            8 => sponge::<u8>(seed, rounds),
+            16 => sponge::<u16>(seed, rounds),
+            32 => sponge::<u32>(seed, rounds),
+            _ => sponge::<u64>(seed, rounds),
+        },
+    };
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("keccak_stream: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+    let seed = match &options.seed {
+        Some(seed) => seed.clone(),
+        None => {
+            let seed = rand_keccak::random::<u128>().to_be_bytes().to_vec();
+            eprintln!("keccak_stream: seed {:x}", BitStream::from_bytes(&seed));
+            seed
+        }
+    };
+    let mut fill = match source(&options, &seed) {
+        Ok(fill) => fill,
+        Err(err) => {
+            eprintln!("keccak_stream: {}", err);
+            process::exit(2);
+        }
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut buffer = [0_u8; 4096];
+    let mut remaining = options.bytes;
+    while remaining != Some(0) {
+        fill(&mut buffer);
+        let len = match remaining {
+            Some(left) => left.min(buffer.len() as u64) as usize,
+            None => buffer.len(),
+        };
+        if let Err(err) = out.write_all(&buffer[..len]) {
+            // the reader having seen enough is how an endless stream ends
+            if err.kind() == io::ErrorKind::BrokenPipe {
+                return;
+            }
+            eprintln!("keccak_stream: {}", err);
+            process::exit(1);
+        }
+        remaining = remaining.map(|left| left - len as u64);
+    }
+    if let Err(err) = out.flush() {
+        if err.kind() != io::ErrorKind::BrokenPipe {
+            eprintln!("keccak_stream: {}", err);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! prints or checks checksums like sha3sum and the coreutils *sum tools:
+//!
+//!     keccaksum -a sha3-256 file > file.sha3
+//!     keccaksum -a sha3-256 --check file.sha3
+//!
+//! Files are hashed as they're read, so any size works. In check mode the
+//! output length of shake and k12 comes from each checksum's length.
+
+use rand_keccak::{
+    sha3::{KECCAK_SUFFIX, SHA3_SUFFIX, SHAKE_SUFFIX},
+    KangarooTwelve, Sponge,
+};
+use std::{
+    env,
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Write},
+    process,
+};
+
+const USAGE: &str = "\
+usage: keccaksum [-a ALGORITHM] [-l BITS] [-c [--strict]] [FILE]...
+
+  -a, --algorithm  sha3-224, sha3-256, sha3-384, sha3-512, shake128,
+                   shake256, keccak-224, keccak-256, keccak-384, keccak-512
+                   or k12, default sha3-256
+  -l, --length     output length in bits for shake128, shake256 and k12,
+                   default 256 for shake128 and k12 and 512 for shake256
+  -c, --check      read checksums from the FILEs and check them
+      --strict     with --check, fail on improperly formatted checksum
+                   lines instead of only warning about them
+
+With no FILE, or when FILE is -, read standard input.";
+
+#[derive(Clone, Copy)]
+enum Algorithm {
+    Sha3(usize),
+    Shake(usize),
+    Keccak(usize),
+    K12,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> Option<Self> {
+        return match name {
+            "sha3-224" => Some(Algorithm::Sha3(224)),
+            "sha3-256" => Some(Algorithm::Sha3(256)),
+            "sha3-384" => Some(Algorithm::Sha3(384)),
+            "sha3-512" => Some(Algorithm::Sha3(512)),
+            "shake128" => Some(Algorithm::Shake(128)),
+            "shake256" => Some(Algorithm::Shake(256)),
+            "keccak-224" => Some(Algorithm::Keccak(224)),
+            "keccak-256" => Some(Algorithm::Keccak(256)),
+            "keccak-384" => Some(Algorithm::Keccak(384)),
+            "keccak-512" => Some(Algorithm::Keccak(512)),
+            "k12" => Some(Algorithm::K12),
+            _ => None,
+        };
+    }
+
+    /// output length in bytes when none is given
+    fn default_len(self) -> usize {
+        return match self {
+            Algorithm::Sha3(bits) | Algorithm::Keccak(bits) => bits / 8,
+            Algorithm::Shake(security) => security / 4,
+            Algorithm::K12 => 32,
+        };
+    }
+
+    fn is_xof(self) -> bool {
+        return matches!(self, Algorithm::Shake(_) | Algorithm::K12);
+    }
+
+    fn hasher(self) -> Hasher {
+        return match self {
+            Algorithm::Sha3(bits) => Hasher::Sponge(Sponge::new(200 - bits / 4), SHA3_SUFFIX),
+            Algorithm::Shake(security) => {
+                Hasher::Sponge(Sponge::new(200 - security / 4), SHAKE_SUFFIX)
+            }
+            Algorithm::Keccak(bits) => Hasher::Sponge(Sponge::new(200 - bits / 4), KECCAK_SUFFIX),
+            Algorithm::K12 => Hasher::K12(KangarooTwelve::new()),
+        };
+    }
+}
+
+/// only one is alive at a time, so the size of K12 doesn't matter
+#[allow(clippy::large_enum_variant)]
+enum Hasher {
+    Sponge(Sponge<u64>, u8),
+    K12(KangarooTwelve),
+}
+
+impl Hasher {
+    fn absorb(&mut self, input: &[u8]) {
+        match self {
+            Hasher::Sponge(sponge, _) => sponge.absorb(input),
+            Hasher::K12(k) => k.absorb(input),
+        }
+    }
+
+    fn finish(mut self, len: usize) -> Vec<u8> {
+        let mut temp = vec![0; len];
+        match &mut self {
+            Hasher::Sponge(sponge, suffix) => {
+                sponge.finish(*suffix);
+                sponge.squeeze(&mut temp);
+            }
+            Hasher::K12(k) => {
+                k.finish(&[]);
+                k.squeeze(&mut temp);
+            }
+        }
+        return temp;
+    }
+}
+
+struct Options {
+    algorithm: Algorithm,
+    len: Option<usize>,
+    check: bool,
+    strict: bool,
+    files: Vec<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        algorithm: Algorithm::Sha3(256),
+        len: None,
+        check: false,
+        strict: false,
+        files: Vec::new(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "-c" | "--check" => options.check = true,
+            "--strict" => options.strict = true,
+            "-a" | "--algorithm" | "-l" | "--length" => {
+                let val = match args.next() {
+                    Some(val) => val,
+                    None => return Err(format!("{} needs a value", arg)),
+                };
+                if arg == "-a" || arg == "--algorithm" {
+                    options.algorithm = match Algorithm::parse(&val) {
+                        Some(algorithm) => algorithm,
+                        None => return Err(format!("unknown algorithm {}", val)),
+                    };
+                } else {
+                    options.len = match val.parse::<usize>() {
+                        Ok(bits) if bits > 0 && bits.is_multiple_of(8) => Some(bits / 8),
+                        _ => return Err(format!("{} isn't a positive multiple of 8", val)),
+                    };
+                }
+            }
+            "--" => {
+                options.files.extend(args);
+                break;
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option {}", arg));
+            }
+            _ => options.files.push(arg),
+        }
+    }
+    if options.len.is_some() && !options.algorithm.is_xof() {
+        return Err("--length only applies to shake128, shake256 and k12".to_string());
+    }
+    if options.strict && !options.check {
+        return Err("--strict only applies with --check".to_string());
+    }
+    if options.files.is_empty() {
+        options.files.push("-".to_string());
+    }
+    return Ok(options);
+}
+
+fn open(path: &str) -> io::Result<Box<dyn Read>> {
+    if path == "-" {
+        return Ok(Box::new(io::stdin()));
+    }
+    return Ok(Box::new(File::open(path)?));
+}
+
+fn hash_file(path: &str, algorithm: Algorithm, len: usize) -> io::Result<Vec<u8>> {
+    let mut input = open(path)?;
+    let mut hasher = algorithm.hasher();
+    let mut buffer = vec![0; 1 << 16];
+    loop {
+        let read = match input.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        hasher.absorb(&buffer[..read]);
+    }
+    return Ok(hasher.finish(len));
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    return bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+}
+
+/// prints a `hash  filename` line per file to out, false if any couldn't be
+/// read and an error only if out couldn't be written
+fn print_sums(options: &Options, out: &mut impl Write) -> io::Result<bool> {
+    let len = options
+        .len
+        .unwrap_or_else(|| options.algorithm.default_len());
+    let mut ok = true;
+    for path in &options.files {
+        match hash_file(path, options.algorithm, len) {
+            Ok(hash) => writeln!(out, "{}  {}", to_hex(&hash), path)?,
+            Err(err) => {
+                eprintln!("keccaksum: {}: {}", path, err);
+                ok = false;
+            }
+        }
+    }
+    return Ok(ok);
+}
+
+/// splits a `hash  filename` line, also taking the ` *filename` binary
+/// marker the coreutils tools write
+fn parse_line(line: &str) -> Option<(&str, &str)> {
+    let (hash, name) = line.split_once(' ')?;
+    let name = name.strip_prefix(' ').or_else(|| name.strip_prefix('*'))?;
+    let valid = !hash.is_empty()
+        && hash.len().is_multiple_of(2)
+        && hash.bytes().all(|byte| byte.is_ascii_hexdigit());
+    if !valid || name.is_empty() {
+        return None;
+    }
+    return Some((hash, name));
+}
+
+/// checks every line of every checksum file, false on any failure or on a
+/// file without a single checksum line. Like the coreutils tools, empty
+/// lines are skipped, a trailing \r is ignored, so lists written on
+/// Windows check too, and improperly formatted lines only fail with
+/// --strict.
+fn check_sums(options: &Options, out: &mut impl Write) -> io::Result<bool> {
+    let (mut failed, mut unreadable, mut improper, mut empty) = (0, 0, 0, 0);
+    for list in &options.files {
+        let lines = match open(list) {
+            Ok(input) => BufReader::new(input).lines(),
+            Err(err) => {
+                eprintln!("keccaksum: {}: {}", list, err);
+                unreadable += 1;
+                continue;
+            }
+        };
+        let mut proper = 0;
+        for line in lines {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    eprintln!("keccaksum: {}: {}", list, err);
+                    unreadable += 1;
+                    break;
+                }
+            };
+            let line = line.strip_suffix('\r').unwrap_or(&line);
+            if line.is_empty() {
+                continue;
+            }
+            let (expected, path) = match parse_line(line) {
+                Some(parsed) => parsed,
+                None => {
+                    improper += 1;
+                    continue;
+                }
+            };
+            let len = expected.len() / 2;
+            if !options.algorithm.is_xof() && len != options.algorithm.default_len() {
+                improper += 1;
+                continue;
+            }
+            proper += 1;
+            match hash_file(path, options.algorithm, len) {
+                Ok(hash) if to_hex(&hash).eq_ignore_ascii_case(expected) => {
+                    writeln!(out, "{}: OK", path)?
+                }
+                Ok(_) => {
+                    writeln!(out, "{}: FAILED", path)?;
+                    failed += 1;
+                }
+                Err(err) => {
+                    eprintln!("keccaksum: {}: {}", path, err);
+                    writeln!(out, "{}: FAILED open or read", path)?;
+                    unreadable += 1;
+                }
+            }
+        }
+        if proper == 0 {
+            eprintln!(
+                "keccaksum: {}: no properly formatted checksum lines found",
+                list
+            );
+            empty += 1;
+        }
+    }
+    if improper > 0 {
+        eprintln!(
+            "keccaksum: WARNING: {} lines are improperly formatted",
+            improper
+        );
+    }
+    if unreadable > 0 {
+        eprintln!("keccaksum: WARNING: {} files could not be read", unreadable);
+    }
+    if failed > 0 {
+        eprintln!(
+            "keccaksum: WARNING: {} computed checksums did NOT match",
+            failed
+        );
+    }
+    return Ok(failed == 0 && unreadable == 0 && empty == 0 && (improper == 0 || !options.strict));
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("keccaksum: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let ok = if options.check {
+        check_sums(&options, &mut out)
+    } else {
+        print_sums(&options, &mut out)
+    };
+    match ok.and_then(|ok| out.flush().map(|_| ok)) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        // the reader went away, eg. `keccaksum -c list | head -1`
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+        Err(err) => {
+            eprintln!("keccaksum: write error: {}", err);
+            process::exit(1);
+        }
+    }
+}
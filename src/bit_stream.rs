@@ -1,15 +1,57 @@
 /// bits should only be 1 or 0
 type Bit = u8;
 
-use std::{
+use crate::{
+    wipe::{wipe_vec_u64, wipe_vec_u8},
+    Error,
+};
+use alloc::vec::Vec;
+use core::{
+    cmp::Ordering,
     convert::Into,
     convert::TryInto,
-    fmt::{Debug, Display, Error, Formatter},
-    mem::size_of,
-    ops::Index,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    iter::FromIterator,
+    mem::{size_of, size_of_val},
+    ops::{
+        Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Bound,
+        Index, Not, RangeBounds,
+    },
 };
 
-#[derive(Debug, PartialEq)]
+/// how the bits of a byte or integer map onto the stream
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitOrder {
+    /// the most significant bit comes first, the crate's default
+    MsbFirst,
+    /// the least significant bit comes first, as in FIPS 202
+    LsbFirst,
+}
+
+/// byte order of integers read out of a stream. The bits of each byte are
+/// always taken MSB-first, Little only reverses the order of the bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endian {
+    Big,
+    Little,
+}
+
+/// read_u8 to read_u128, the size_of::<T>() * 8 bits from offset
+macro_rules! impl_read {
+    ($($name:ident: $T:ty),*) => {
+        $(
+            pub fn $name(&self, offset: usize, endian: Endian) -> Result<$T, Error> {
+                return Ok(self.read_bits(offset, <$T>::BITS as usize, endian)? as $T);
+            }
+        )*
+    };
+}
+
+/// bits are packed MSB-first into exactly ceil(length / 64) words, and any
+/// bits of the last word past length are zero. Every constructor and
+/// mutation keeps it that way, so equal streams have equal words.
+#[derive(Debug)]
 pub struct BitStream {
     bits: Vec<u64>,
     length: usize,
@@ -19,8 +61,8 @@ impl BitStream {
     const ONE: u8 = 1;
     const ZERO: u8 = 0;
 
-    /// for i64 and usize, bits are in "in-memory" order, ie. get(0) returns MSB
-    /// depending (i think... maybe) on the endianess of the system
+    /// MSB-first, get(0) returns the most significant bit of src[0] whatever
+    /// the endianness of the system
     pub fn from_u64(src: &[u64]) -> Self {
         let mut temp = BitStream {
             bits: Vec::new(),
@@ -30,172 +72,906 @@ impl BitStream {
         return temp;
     }
 
+    pub fn from_u64_with_order(src: &[u64], order: BitOrder) -> Self {
+        return BitStream::from_val_with_order(src, order);
+    }
+
+    /// each value is written as its size_of::<T>() bytes, most significant
+    /// byte first with each byte MSB-first
     pub fn from_val<T>(src: &[T]) -> Self
     where
         T: Into<u64> + Copy,
     {
-        let mut temp = BitStream {
-            bits: Vec::<u64>::new(),
-            length: size_of::<T>() * 8 * src.len(),
-        };
-        temp.bits.resize(
-            f64::ceil((size_of::<T>() * src.len()) as f64 / 8.0) as usize,
-            0,
+        return BitStream::from_val_with_order(src, BitOrder::MsbFirst);
+    }
+
+    /// from_val in either order. LsbFirst writes the bytes least significant
+    /// first, so bit i of a value is bit i of its part of the stream, the
+    /// way FIPS 202 maps lanes to bit strings.
+    pub fn from_val_with_order<T>(src: &[T], order: BitOrder) -> Self
+    where
+        T: Into<u64> + Copy,
+    {
+        return BitStream::pack(
+            src.iter().map(|&item| item.into()),
+            size_of::<T>(),
+            src.len(),
+            order,
         );
-        let mut idx = 0;
-        let mut b_idx: i32 = 56;
-        for src_idx in 0..src.len() {
-            for src_b_idx in (0..size_of::<T>()).rev() {
-                temp.bits[idx] |= ((0xFF << src_b_idx * 8) & src[src_idx].into())
-                    << (b_idx as usize - src_b_idx * 8);
-                b_idx -= 8;
-                if b_idx < 0 {
-                    b_idx = 56;
-                    idx += 1;
-                }
-            }
-        }
-        return temp;
     }
 
-    pub fn try_from_val<T>(src: &[T]) -> Self
+    /// like from_val, for types that may not fit in a u64. Fails with
+    /// Error::Conversion if any value doesn't.
+    pub fn try_from_val<T>(src: &[T]) -> Result<Self, Error>
     where
         T: TryInto<u64> + Copy,
-        <T as TryInto<u64>>::Error: Debug,
     {
-        let mut temp = BitStream {
-            bits: Vec::<u64>::new(),
-            length: size_of::<T>() * 8 * src.len(),
-        };
-        temp.bits.resize(
-            f64::ceil((size_of::<T>() * src.len()) as f64 / 8.0) as usize,
-            0,
-        );
-        let mut idx = 0;
-        let mut b_idx: i32 = 56;
-        for src_idx in 0..src.len() {
-            for src_b_idx in (0..size_of::<T>()).rev() {
-                temp.bits[idx] |= ((0xFFu64 << src_b_idx * 8) & src[src_idx].try_into().unwrap())
-                    << (b_idx as usize - src_b_idx * 8);
-                b_idx -= 8;
-                if b_idx < 0 {
-                    b_idx = 56;
-                    idx += 1;
-                }
-            }
+        let mut temp = BitStream::new(8 * size_of_val(src));
+        for (idx, &item) in src.iter().enumerate() {
+            let val: u64 = item.try_into().map_err(|_| Error::Conversion)?;
+            temp.write_bytes(
+                idx * size_of::<T>(),
+                val,
+                size_of::<T>(),
+                BitOrder::MsbFirst,
+            );
+        }
+        return Ok(temp);
+    }
+
+    /// bytes of count values of size bytes each
+    fn pack<I: Iterator<Item = u64>>(src: I, size: usize, count: usize, order: BitOrder) -> Self {
+        let mut temp = BitStream::new(8 * size * count);
+        for (idx, val) in src.enumerate() {
+            temp.write_bytes(idx * size, val, size, order);
         }
         return temp;
     }
 
-    pub fn add_val<T>(&mut self, src: &[T]) -> ()
+    /// ors the size bytes of val into the stream starting at byte pos. Bytes
+    /// past the 8 of a u64 are zero.
+    fn write_bytes(&mut self, pos: usize, val: u64, size: usize, order: BitOrder) {
+        for idx in 0..size {
+            let byte = match order {
+                BitOrder::MsbFirst if size - 1 - idx < 8 => (val >> (8 * (size - 1 - idx))) as u8,
+                BitOrder::LsbFirst if idx < 8 => ((val >> (8 * idx)) as u8).reverse_bits(),
+                _ => 0,
+            };
+            let byte_pos = pos + idx;
+            self.bits[byte_pos / 8] |= (byte as u64) << (56 - 8 * (byte_pos % 8));
+        }
+    }
+
+    /// appends the bytes of each value as from_val writes them, at any bit
+    /// offset
+    pub fn add_val<T>(&mut self, src: &[T])
     where
         T: Into<u64> + Copy,
     {
-        let mut idx = self.bits.len() - 1;
-        let mut b_idx: i32 = (self.len() as u64 % 64) as i32;
-        assert_eq!(b_idx % 8, 0);
-        for src_idx in 0..src.len() {
-            for src_b_idx in (0..size_of::<T>()).rev() {
-                self.bits[idx] |= ((0xFFu64 << src_b_idx * 8) & src[src_idx].into())
-                    << (b_idx as usize - src_b_idx * 8);
-                b_idx -= 8;
-                if b_idx < 0 {
-                    b_idx = 56;
-                    idx += 1;
-                    self.bits.push(0);
-                }
-            }
-        }
+        self.append(&BitStream::from_val(src));
     }
 
-    pub fn try_add_val<T>(&mut self, src: &[T]) -> ()
+    /// like add_val, for types that may not fit in a u64. Nothing is
+    /// appended if any value doesn't fit.
+    pub fn try_add_val<T>(&mut self, src: &[T]) -> Result<(), Error>
     where
         T: TryInto<u64> + Copy,
-        <T as TryInto<u64>>::Error: Debug,
     {
-        let mut idx = self.bits.len() - 1;
-        let mut b_idx: i32 = (self.len() as u64 % 64) as i32;
-        assert_eq!(b_idx % 8, 0);
-        for src_idx in 0..src.len() {
-            for src_b_idx in (0..size_of::<T>()).rev() {
-                self.bits[idx] |= ((0xFFu64 << src_b_idx * 8) & src[src_idx].try_into().unwrap())
-                    << (b_idx as usize - src_b_idx * 8);
-                b_idx -= 8;
-                if b_idx < 0 {
-                    b_idx = 56;
-                    idx += 1;
-                    self.bits.push(0);
-                }
-            }
+        self.append(&BitStream::try_from_val(src)?);
+        return Ok(());
+    }
+
+    pub fn push(&mut self, bit: Bit) {
+        self.grow((self.length + 1).div_ceil(64));
+        self.length += 1;
+        self.set(self.length - 1, bit);
+    }
+
+    /// pushes each bit, only the least significant bit of each has any
+    /// effect
+    pub fn extend_bits<I: IntoIterator<Item = Bit>>(&mut self, bits: I) {
+        let bits = bits.into_iter();
+        self.grow((self.length + bits.size_hint().0).div_ceil(64));
+        for bit in bits {
+            self.push(bit);
         }
     }
 
-    pub fn from_str<'a>(src: &'a str) -> Self {
-        let mut temp = BitStream {
-            bits: Vec::new(),
-            length: src.len() * 8,
-        };
-        temp.bits
-            .resize(f64::ceil(src.len() as f64 / 8.0) as usize, 0);
-        let mut idx = 0;
-        let mut b_idx: i32 = 56;
-        for byte in src.as_bytes() {
-            temp.bits[idx] |= (*byte as u64) << b_idx;
-            b_idx -= 8;
-            if b_idx < 0 {
-                b_idx = 56;
-                idx += 1;
+    /// appends the bits of other, at any bit offset
+    pub fn append(&mut self, other: &BitStream) {
+        self.append_words(other.words(), other.length);
+    }
+
+    /// appends the bits of a view, at any bit offset
+    pub fn extend_from_slice(&mut self, other: BitSlice<'_>) {
+        self.append_words(other.words(), other.len());
+    }
+
+    /// words must hold len bits MSB-first, with any bits past len zero
+    fn append_words<I: Iterator<Item = u64>>(&mut self, words: I, len: usize) {
+        let offset = self.length % 64;
+        let start = self.length / 64;
+        self.grow((self.length + len).div_ceil(64) + 1);
+        for (idx, word) in words.enumerate() {
+            if offset == 0 {
+                self.bits[start + idx] = word;
+            } else {
+                self.bits[start + idx] |= word >> offset;
+                self.bits[start + idx + 1] = word << (64 - offset);
             }
         }
+        self.length += len;
+        self.bits.truncate(self.length.div_ceil(64));
+    }
+
+    /// self followed by other, in a new stream
+    pub fn concat(&self, other: &BitStream) -> BitStream {
+        let mut temp = BitStream {
+            bits: Vec::with_capacity((self.length + other.length).div_ceil(64) + 1),
+            length: self.length,
+        };
+        temp.bits.extend(self.words());
+        temp.append(other);
         return temp;
     }
 
+    /// borrowed view of the bits in range, panics if it isn't inside the
+    /// stream
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> BitSlice<'_> {
+        return self.as_bit_slice().slice(range);
+    }
+
+    pub fn try_slice<R: RangeBounds<usize>>(&self, range: R) -> Result<BitSlice<'_>, Error> {
+        return self.as_bit_slice().try_slice(range);
+    }
+
+    /// views of the bits before mid and from mid on
+    pub fn split_at(&self, mid: usize) -> (BitSlice<'_>, BitSlice<'_>) {
+        return self.as_bit_slice().split_at(mid);
+    }
+
+    pub fn as_bit_slice(&self) -> BitSlice<'_> {
+        return BitSlice {
+            stream: self,
+            start: 0,
+            len: self.length,
+        };
+    }
+
+    /// keeps the first len bits, does nothing if there are already fewer
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.length {
+            self.length = len;
+            self.clear_tail();
+        }
+    }
+
+    impl_read!(read_u8: u8, read_u16: u16, read_u32: u32, read_u64: u64, read_u128: u128);
+
+    /// the count bits from offset as an integer, count must be a multiple
+    /// of 8 no larger than 128
+    fn read_bits(&self, offset: usize, count: usize, endian: Endian) -> Result<u128, Error> {
+        let end = offset.saturating_add(count);
+        if end > self.length {
+            return Err(Error::OutOfBounds {
+                index: end,
+                len: self.length,
+            });
+        }
+        let mut words = self.slice(offset..end).words();
+        let high = words.next().unwrap_or(0) as u128;
+        let low = words.next().unwrap_or(0) as u128;
+        let val = ((high << 64) | low) >> (128 - count);
+        return Ok(match endian {
+            Endian::Big => val,
+            Endian::Little => val.swap_bytes() >> (128 - count),
+        });
+    }
+
+    pub fn count_ones(&self) -> usize {
+        return self.words().map(|word| word.count_ones() as usize).sum();
+    }
+
+    /// number of positions at which the bits differ, panics if the lengths
+    /// don't match, see try_hamming_distance
+    pub fn hamming_distance(&self, other: &BitStream) -> usize {
+        return match self.try_hamming_distance(other) {
+            Ok(distance) => distance,
+            Err(err) => panic!("{}", err),
+        };
+    }
+
+    /// like hamming_distance, but different lengths are Error::Mismatch
+    pub fn try_hamming_distance(&self, other: &BitStream) -> Result<usize, Error> {
+        self.check_len(other)?;
+        return Ok(self
+            .words()
+            .zip(other.words())
+            .map(|(a, b)| (a ^ b).count_ones() as usize)
+            .sum());
+    }
+
+    /// self ^ other, but different lengths are Error::Mismatch instead of
+    /// a panic
+    pub fn try_xor(&self, other: &BitStream) -> Result<BitStream, Error> {
+        return self.try_combine(other, |a, b| a ^ b);
+    }
+
+    /// self & other, but different lengths are Error::Mismatch instead of
+    /// a panic
+    pub fn try_and(&self, other: &BitStream) -> Result<BitStream, Error> {
+        return self.try_combine(other, |a, b| a & b);
+    }
+
+    /// self | other, but different lengths are Error::Mismatch instead of
+    /// a panic
+    pub fn try_or(&self, other: &BitStream) -> Result<BitStream, Error> {
+        return self.try_combine(other, |a, b| a | b);
+    }
+
+    /// rotates in place so that bit n comes first, like slice::rotate_left
+    pub fn rotate_left(&mut self, n: usize) {
+        if self.length == 0 {
+            return;
+        }
+        let n = n % self.length;
+        let mut temp = BitStream {
+            bits: Vec::with_capacity(self.length.div_ceil(64) + 1),
+            length: 0,
+        };
+        temp.extend_from_slice(self.slice(n..));
+        temp.extend_from_slice(self.slice(..n));
+        *self = temp;
+    }
+
+    /// rotates in place so that the last n bits come first
+    pub fn rotate_right(&mut self, n: usize) {
+        if self.length == 0 {
+            return;
+        }
+        self.rotate_left(self.length - n % self.length);
+    }
+
+    fn check_len(&self, other: &BitStream) -> Result<(), Error> {
+        if self.length != other.length {
+            return Err(Error::Mismatch {
+                left: self.length,
+                right: other.length,
+            });
+        }
+        return Ok(());
+    }
+
+    /// word by word op, panics unless both have the same length
+    fn combine(&self, other: &BitStream, op: impl Fn(u64, u64) -> u64) -> BitStream {
+        return match self.try_combine(other, op) {
+            Ok(temp) => temp,
+            Err(err) => panic!("{}", err),
+        };
+    }
+
+    fn try_combine(
+        &self,
+        other: &BitStream,
+        op: impl Fn(u64, u64) -> u64,
+    ) -> Result<BitStream, Error> {
+        self.check_len(other)?;
+        return Ok(BitStream {
+            bits: self
+                .words()
+                .zip(other.words())
+                .map(|(a, b)| op(a, b))
+                .collect(),
+            length: self.length,
+        });
+    }
+
+    fn combine_assign(&mut self, other: &BitStream, op: impl Fn(u64, u64) -> u64) {
+        if let Err(err) = self.check_len(other) {
+            panic!("{}", err);
+        }
+        for (word, other) in self.bits.iter_mut().zip(other.words()) {
+            *word = op(*word, other);
+        }
+    }
+
+    /// makes sure there are at least words words, moving to a new
+    /// allocation by hand so the old one can be wiped before it is freed
+    fn grow(&mut self, words: usize) {
+        if words > self.bits.capacity() {
+            let mut temp = Vec::with_capacity(words.max(2 * self.bits.capacity()));
+            temp.extend_from_slice(&self.bits);
+            wipe_vec_u64(&mut self.bits);
+            self.bits = temp;
+        }
+        if words > self.bits.len() {
+            self.bits.resize(words, 0);
+        }
+    }
+
     /// new bitstream of length zero-initialized bits
     pub fn new(length: usize) -> Self {
         let mut temp = BitStream {
             bits: Vec::new(),
-            length: length,
+            length,
         };
-        temp.bits.resize(f64::ceil(length as f64 / 8.0) as usize, 0);
+        temp.bits.resize(length.div_ceil(64), 0);
         return temp;
     }
 
     pub fn get(&self, idx: usize) -> Bit {
-        if idx >= self.length {
-            panic!("BitStream index out of bounds")
+        return match self.try_get(idx) {
+            Ok(bit) => bit,
+            Err(err) => panic!("BitStream {}", err),
         };
-        return (self.bits[idx / 64] >> (63 - idx % 64)) as u8 & 1;
+    }
+
+    pub fn try_get(&self, idx: usize) -> Result<Bit, Error> {
+        if idx >= self.length {
+            return Err(Error::OutOfBounds {
+                index: idx,
+                len: self.length,
+            });
+        }
+        return Ok((self.bits[idx / 64] >> (63 - idx % 64)) as u8 & 1);
     }
 
     /// only the least significant bit of val has any effect, so function is
     /// safe to use even if val isn't a true bit.
     pub fn set(&mut self, idx: usize, val: Bit) {
-        let setter: u64 = 0x7FFFFFFFFFFFFFFF;
-        self.bits[idx / 64] =
-            (self.bits[idx / 64] & (setter >> (idx % 64))) | ((val as u64 & 1) << 63 - idx % 64);
+        if let Err(err) = self.try_set(idx, val) {
+            panic!("BitStream {}", err);
+        }
+    }
+
+    pub fn try_set(&mut self, idx: usize, val: Bit) -> Result<(), Error> {
+        if idx >= self.length {
+            return Err(Error::OutOfBounds {
+                index: idx,
+                len: self.length,
+            });
+        }
+        let mask: u64 = 1 << (63 - idx % 64);
+        self.bits[idx / 64] = (self.bits[idx / 64] & !mask) | ((val as u64 & 1) << (63 - idx % 64));
+        return Ok(());
+    }
+
+    /// bytes[0] holds the first 8 bits, each byte MSB-first
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut temp = BitStream {
+            bits: Vec::with_capacity(bytes.len().div_ceil(8)),
+            length: 8 * bytes.len(),
+        };
+        for chunk in bytes.chunks(8) {
+            let mut word = [0_u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            temp.bits.push(u64::from_be_bytes(word));
+        }
+        return temp;
+    }
+
+    pub fn from_bytes_with_order(bytes: &[u8], order: BitOrder) -> Self {
+        let mut temp = BitStream::from_bytes(bytes);
+        if order == BitOrder::LsbFirst {
+            temp.reverse_bytes();
+        }
+        return temp;
+    }
+
+    /// like from_bytes, keeping only the first bit_len bits. bytes must be
+    /// exactly ceil(bit_len / 8) long, unused low bits of the last byte are
+    /// ignored.
+    pub fn from_partial_bytes(bytes: &[u8], bit_len: usize) -> Result<Self, Error> {
+        if bytes.len() != bit_len.div_ceil(8) {
+            return Err(Error::Length {
+                expected: bit_len.div_ceil(8),
+                actual: bytes.len(),
+            });
+        }
+        let mut temp = BitStream::from_bytes(bytes);
+        temp.length = bit_len;
+        temp.clear_tail();
+        return Ok(temp);
+    }
+
+    /// inverse of from_bytes and from_partial_bytes, a partial final byte is
+    /// padded with zero bits
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut temp = Vec::with_capacity(8 * self.length.div_ceil(64));
+        for word in self.words() {
+            temp.extend_from_slice(&word.to_be_bytes());
+        }
+        temp.truncate(self.byte_len());
+        return temp;
+    }
+
+    pub fn to_bytes_with_order(&self, order: BitOrder) -> Vec<u8> {
+        let mut temp = self.to_bytes();
+        if order == BitOrder::LsbFirst {
+            for byte in temp.iter_mut() {
+                *byte = byte.reverse_bits();
+            }
+        }
+        return temp;
+    }
+
+    /// the stream read from bytes in order from, as if they had been read
+    /// in order to instead. Only whole bytes can be reordered, so the
+    /// length must be a multiple of 8 unless the orders match.
+    pub fn convert_order(&self, from: BitOrder, to: BitOrder) -> Result<Self, Error> {
+        let mut temp = BitStream {
+            bits: self.words().collect(),
+            length: self.length,
+        };
+        if from != to {
+            if !self.length.is_multiple_of(8) {
+                return Err(Error::Unaligned { len: self.length });
+            }
+            temp.reverse_bytes();
+        }
+        return Ok(temp);
+    }
+
+    /// reverses the bits of every byte
+    fn reverse_bytes(&mut self) {
+        for word in self.bits.iter_mut() {
+            *word = word.reverse_bits().swap_bytes();
+        }
     }
 
     pub fn len(&self) -> usize {
         return self.length;
     }
 
+    /// same as len
+    pub fn bit_len(&self) -> usize {
+        return self.length;
+    }
+
+    /// bytes needed to hold every bit, ie. the length of to_bytes
+    pub fn byte_len(&self) -> usize {
+        return self.length.div_ceil(8);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.length == 0;
+    }
+
+    /// the bits as ceil(len / 64) words, MSB-first, with any bits past len
+    /// zeroed
     pub fn as_vec_u64(&self) -> Vec<u64> {
-        return self.bits.clone();
+        return self.words().collect();
+    }
+
+    /// iterates over the bits, a word at a time underneath
+    pub fn iter(&self) -> Iter<'_> {
+        return self.as_bit_slice().iter();
+    }
+
+    /// the bytes of to_bytes, without collecting them
+    pub fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        let len = self.byte_len();
+        return self.words().flat_map(u64::to_be_bytes).take(len);
+    }
+
+    /// views of size bits each, the last one may be shorter. Panics if size
+    /// is 0.
+    pub fn chunks(&self, size: usize) -> impl Iterator<Item = BitSlice<'_>> {
+        return self.as_bit_slice().chunks(size);
+    }
+
+    /// the bits as ceil(len / 64) words, MSB-first, with any bits past len
+    /// zeroed
+    pub fn words(&self) -> impl Iterator<Item = u64> + '_ {
+        let count = self.length.div_ceil(64);
+        let tail = self.length % 64;
+        return self.bits[..count]
+            .iter()
+            .enumerate()
+            .map(move |(idx, &word)| {
+                if idx + 1 == count && tail != 0 {
+                    return word & !(u64::MAX >> tail);
+                }
+                return word;
+            });
+    }
+
+    /// drops words past len and zeroes the bits of the last one past len.
+    /// Dropped words stay in spare capacity until Drop wipes it.
+    fn clear_tail(&mut self) {
+        let count = self.length.div_ceil(64);
+        let tail = self.length % 64;
+        if tail != 0 {
+            self.bits[count - 1] &= !(u64::MAX >> tail);
+        }
+        for word in self.bits[count..].iter_mut() {
+            *word = 0;
+        }
+        self.bits.truncate(count);
+    }
+}
+
+/// equality, ordering and hashing only look at the bits up to len
+impl PartialEq for BitStream {
+    fn eq(&self, other: &Self) -> bool {
+        return self.length == other.length && self.words().eq(other.words());
     }
 }
 
-impl Display for BitStream {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        for idx in 0..self.len() {
-            match write!(f, "{:b}", self.bits[idx]) {
-                Err(err) => return Err(err),
-                _ => continue,
+impl Eq for BitStream {}
+
+/// lexicographic by bit, a stream that is a prefix of another is less
+impl Ord for BitStream {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // bits past len are zero, so the first differing word decides unless
+        // one stream is the other followed by zeros
+        return self
+            .words()
+            .cmp(other.words())
+            .then(self.length.cmp(&other.length));
+    }
+}
+
+impl PartialOrd for BitStream {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl Hash for BitStream {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.length.hash(state);
+        for word in self.words() {
+            word.hash(state);
+        }
+    }
+}
+
+impl Clone for BitStream {
+    fn clone(&self) -> Self {
+        return BitStream {
+            bits: self.words().collect(),
+            length: self.length,
+        };
+    }
+}
+
+impl Add<&BitStream> for &BitStream {
+    type Output = BitStream;
+
+    fn add(self, other: &BitStream) -> BitStream {
+        return self.concat(other);
+    }
+}
+
+impl Add<&BitStream> for BitStream {
+    type Output = BitStream;
+
+    fn add(mut self, other: &BitStream) -> BitStream {
+        self.append(other);
+        return self;
+    }
+}
+
+impl AddAssign<&BitStream> for BitStream {
+    fn add_assign(&mut self, other: &BitStream) {
+        self.append(other);
+    }
+}
+
+/// &a ^ &b, a ^= &b and the same for & and |, word at a time. Both sides
+/// must have the same length, a mismatch panics, see try_xor, try_and and
+/// try_or.
+macro_rules! impl_bit_op {
+    ($Op:ident, $op:ident, $OpAssign:ident, $op_assign:ident, $f:expr) => {
+        impl $Op<&BitStream> for &BitStream {
+            type Output = BitStream;
+
+            fn $op(self, other: &BitStream) -> BitStream {
+                return self.combine(other, $f);
+            }
+        }
+
+        impl $Op<&BitStream> for BitStream {
+            type Output = BitStream;
+
+            fn $op(mut self, other: &BitStream) -> BitStream {
+                self.combine_assign(other, $f);
+                return self;
+            }
+        }
+
+        impl $OpAssign<&BitStream> for BitStream {
+            fn $op_assign(&mut self, other: &BitStream) {
+                self.combine_assign(other, $f);
             }
         }
-        match write!(f, "\n") {
-            Err(err) => Err(err),
-            _ => Ok(()),
+    };
+}
+
+impl_bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, |a, b| a ^ b);
+impl_bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign, |a, b| a & b);
+impl_bit_op!(BitOr, bitor, BitOrAssign, bitor_assign, |a, b| a | b);
+
+impl Not for &BitStream {
+    type Output = BitStream;
+
+    fn not(self) -> BitStream {
+        let mut temp = BitStream {
+            bits: self.words().map(|word| !word).collect(),
+            length: self.length,
+        };
+        temp.clear_tail();
+        return temp;
+    }
+}
+
+impl Not for BitStream {
+    type Output = BitStream;
+
+    fn not(mut self) -> BitStream {
+        for word in self.bits.iter_mut() {
+            *word = !*word;
         }
+        self.clear_tail();
+        return self;
+    }
+}
+
+/// borrowed view of bits start..start + len of a BitStream
+#[derive(Clone, Copy, Debug)]
+pub struct BitSlice<'a> {
+    stream: &'a BitStream,
+    start: usize,
+    len: usize,
+}
+
+impl<'a> BitSlice<'a> {
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    pub fn get(&self, idx: usize) -> Bit {
+        return match self.try_get(idx) {
+            Ok(bit) => bit,
+            Err(err) => panic!("BitSlice {}", err),
+        };
+    }
+
+    pub fn try_get(&self, idx: usize) -> Result<Bit, Error> {
+        if idx >= self.len {
+            return Err(Error::OutOfBounds {
+                index: idx,
+                len: self.len,
+            });
+        }
+        return self.stream.try_get(self.start + idx);
+    }
+
+    /// view of range within this view, panics if it isn't inside it
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> BitSlice<'a> {
+        return match self.try_slice(range) {
+            Ok(view) => view,
+            Err(err) => panic!("BitSlice {}", err),
+        };
+    }
+
+    pub fn try_slice<R: RangeBounds<usize>>(&self, range: R) -> Result<BitSlice<'a>, Error> {
+        let out_of_bounds = Error::OutOfBounds {
+            index: usize::MAX,
+            len: self.len,
+        };
+        let start = match range.start_bound() {
+            Bound::Included(&idx) => idx,
+            Bound::Excluded(&idx) => idx.checked_add(1).ok_or(out_of_bounds.clone())?,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&idx) => idx.checked_add(1).ok_or(out_of_bounds)?,
+            Bound::Excluded(&idx) => idx,
+            Bound::Unbounded => self.len,
+        };
+        if end > self.len {
+            return Err(Error::OutOfBounds {
+                index: end,
+                len: self.len,
+            });
+        }
+        if start > end {
+            return Err(Error::OutOfBounds {
+                index: start,
+                len: end,
+            });
+        }
+        return Ok(BitSlice {
+            stream: self.stream,
+            start: self.start + start,
+            len: end - start,
+        });
+    }
+
+    pub fn split_at(&self, mid: usize) -> (BitSlice<'a>, BitSlice<'a>) {
+        return (self.slice(..mid), self.slice(mid..));
+    }
+
+    pub fn count_ones(&self) -> usize {
+        return self.words().map(|word| word.count_ones() as usize).sum();
+    }
+
+    /// copies the viewed bits into a stream of their own
+    pub fn to_bit_stream(&self) -> BitStream {
+        return BitStream {
+            bits: self.words().collect(),
+            length: self.len,
+        };
+    }
+
+    pub fn iter(&self) -> Iter<'a> {
+        let bits: &'a [u64] = &self.stream.bits;
+        let mut word = 0;
+        if !self.start.is_multiple_of(64) && self.len > 0 {
+            word = bits[self.start / 64] << (self.start % 64);
+        }
+        return Iter {
+            bits,
+            word,
+            pos: self.start,
+            end: self.start + self.len,
+        };
+    }
+
+    /// the viewed bits packed MSB-first into bytes, a partial last byte is
+    /// padded with zero bits
+    pub fn bytes(&self) -> impl Iterator<Item = u8> + 'a {
+        let len = self.len.div_ceil(8);
+        return self.words().flat_map(u64::to_be_bytes).take(len);
+    }
+
+    /// views of size bits each, the last one may be shorter. Panics if size
+    /// is 0.
+    pub fn chunks(&self, size: usize) -> impl Iterator<Item = BitSlice<'a>> {
+        assert!(size != 0, "chunk size must be non-zero");
+        let view = *self;
+        return (0..self.len)
+            .step_by(size)
+            .map(move |start| view.slice(start..view.len.min(start + size)));
+    }
+
+    /// the viewed bits shifted to start at a word boundary, MSB-first, with
+    /// bits past the end of the view masked off
+    pub fn words(&self) -> impl Iterator<Item = u64> + 'a {
+        let bits: &'a [u64] = &self.stream.bits;
+        let start = self.start;
+        let len = self.len;
+        return (0..len.div_ceil(64)).map(move |idx| {
+            let pos = start + 64 * idx;
+            let (word_idx, offset) = (pos / 64, pos % 64);
+            let mut word = bits[word_idx] << offset;
+            if offset != 0 && word_idx + 1 < bits.len() {
+                word |= bits[word_idx + 1] >> (64 - offset);
+            }
+            let remaining = len - 64 * idx;
+            if remaining < 64 {
+                word &= !(u64::MAX >> remaining);
+            }
+            return word;
+        });
+    }
+}
+
+/// iterator over the bits of a BitStream or BitSlice. Keeps the current
+/// word shifted so the next bit is its MSB, so each step is a shift.
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    bits: &'a [u64],
+    word: u64,
+    pos: usize,
+    end: usize,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = Bit;
+
+    fn next(&mut self) -> Option<Bit> {
+        if self.pos == self.end {
+            return None;
+        }
+        if self.pos.is_multiple_of(64) {
+            self.word = self.bits[self.pos / 64];
+        }
+        let bit = (self.word >> 63) as Bit;
+        self.word <<= 1;
+        self.pos += 1;
+        return Some(bit);
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        return (self.end - self.pos, Some(self.end - self.pos));
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl<'a> IntoIterator for &'a BitStream {
+    type Item = Bit;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        return self.iter();
+    }
+}
+
+impl<'a> IntoIterator for BitSlice<'a> {
+    type Item = Bit;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        return self.iter();
+    }
+}
+
+/// only the least significant bit of each item has any effect
+impl FromIterator<Bit> for BitStream {
+    fn from_iter<I: IntoIterator<Item = Bit>>(iter: I) -> Self {
+        let mut temp = BitStream::new(0);
+        temp.extend_bits(iter);
+        return temp;
+    }
+}
+
+impl FromIterator<bool> for BitStream {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        return iter.into_iter().map(Bit::from).collect();
+    }
+}
+
+impl Extend<Bit> for BitStream {
+    fn extend<I: IntoIterator<Item = Bit>>(&mut self, iter: I) {
+        self.extend_bits(iter);
+    }
+}
+
+impl Extend<bool> for BitStream {
+    fn extend<I: IntoIterator<Item = bool>>(&mut self, iter: I) {
+        self.extend_bits(iter.into_iter().map(Bit::from));
+    }
+}
+
+impl PartialEq for BitSlice<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        return self.len == other.len && self.words().eq(other.words());
+    }
+}
+
+impl From<&[u8]> for BitStream {
+    fn from(bytes: &[u8]) -> Self {
+        return BitStream::from_bytes(bytes);
+    }
+}
+
+impl From<Vec<u8>> for BitStream {
+    /// the vec is wiped before it is freed, like BitStream's own words
+    fn from(mut bytes: Vec<u8>) -> Self {
+        let temp = BitStream::from_bytes(&bytes);
+        wipe_vec_u8(&mut bytes);
+        return temp;
+    }
+}
+
+impl From<&BitStream> for Vec<u8> {
+    fn from(bits: &BitStream) -> Self {
+        return bits.to_bytes();
+    }
+}
+
+/// bits may be key material, so they are wiped before the memory is freed
+impl Drop for BitStream {
+    fn drop(&mut self) {
+        wipe_vec_u64(&mut self.bits);
     }
 }
 
@@ -203,7 +979,13 @@ impl Index<usize> for BitStream {
     type Output = Bit;
     fn index(&self, idx: usize) -> &Self::Output {
         if idx >= self.length {
-            panic!("BitStream index out of bounds")
+            panic!(
+                "BitStream {}",
+                Error::OutOfBounds {
+                    index: idx,
+                    len: self.length
+                }
+            );
         };
         match (self.bits[idx / 64] >> (63 - idx % 64)) & 1 {
             0 => &BitStream::ZERO,
@@ -0,0 +1,270 @@
+use crate::{
+    wipe::{wipe_vec_u64, wipe_vec_u8},
+    BitStream, Error, Keccak,
+};
+use alloc::vec::Vec;
+
+/// counter-mode generator, block n of the output is the keccak permutation
+/// of the seeded state with n xor'ed into its last 64 bits, so any point of
+/// the stream can be reached without generating the values before it.
+pub struct KeccakCtr {
+    key: BitStream,
+    w: usize,
+    rounds: u64,
+    position: u64,
+    reseeds: u64,
+    block: Option<u64>,
+    buffer: Vec<u64>,
+}
+
+/// first bytes of every saved KeccakCtr, followed by the format version
+const MAGIC: &[u8; 4] = b"KCTR";
+const VERSION: u8 = 1;
+/// magic, version, depth, rounds, position and reseed count
+const HEADER_LEN: usize = 4 + 1 + 1 + 1 + 8 + 8;
+
+impl KeccakCtr {
+    /// the state is seeded the same way as Keccak::new_sized, depth must be
+    /// one of 8, 16, 32 or 64 so that the counter fits in the capacity.
+    /// only the first half of each permuted state is output, leaving the
+    /// other half as capacity so the seed can't be recovered by inverting
+    /// the permutation.
+    pub fn new(seed: &BitStream, depth: usize, rounds: u64) -> Self {
+        return match KeccakCtr::try_new(seed, depth, rounds) {
+            Ok(ctr) => ctr,
+            Err(err) => panic!("{}", err),
+        };
+    }
+
+    /// like new, but an unsupported depth, zero rounds or too many rounds is
+    /// an error instead of a panic. With zero rounds the output would be the
+    /// seed itself.
+    pub fn try_new(seed: &BitStream, depth: usize, rounds: u64) -> Result<Self, Error> {
+        if !depth.is_power_of_two() || !(8..=64).contains(&depth) {
+            return Err(Error::InvalidDepth { depth });
+        }
+        if rounds == 0 {
+            return Err(Error::TooFewRounds { rounds, min: 1 });
+        }
+        let l = depth.trailing_zeros() as u64;
+        if rounds > 12 + 2 * l {
+            return Err(Error::TooManyRounds {
+                rounds,
+                max: 12 + 2 * l,
+            });
+        }
+        let mut key = BitStream::new(25 * depth);
+        for idx in 0..seed.len().min(25 * depth) {
+            key.set(idx, seed.get(idx));
+        }
+        return Ok(KeccakCtr {
+            key,
+            w: depth,
+            rounds,
+            position: 0,
+            reseeds: 0,
+            block: None,
+            buffer: Vec::new(),
+        });
+    }
+
+    /// number of u64 output by each permutation
+    pub fn block_len(&self) -> usize {
+        return (25 * self.w / 2) / 64;
+    }
+
+    pub fn depth(&self) -> usize {
+        return self.w;
+    }
+
+    pub fn rounds(&self) -> u64 {
+        return self.rounds;
+    }
+
+    /// index of the next u64 to be returned by next_u64
+    pub fn position(&self) -> u64 {
+        return self.position;
+    }
+
+    /// moves the stream so the next call to next_u64 returns the value at
+    /// position, takes the same time regardless of how far away position is.
+    pub fn seek(&mut self, position: u64) {
+        self.position = position;
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let block_len = self.block_len() as u64;
+        let block = self.position / block_len;
+        if self.block != Some(block) {
+            self.fill_block(block);
+            self.block = Some(block);
+        }
+        let val = self.buffer[(self.position % block_len) as usize];
+        self.position = self.position.wrapping_add(1);
+        return val;
+    }
+
+    /// number of times reseed has been called since the generator was seeded
+    pub fn reseed_count(&self) -> u64 {
+        return self.reseeds;
+    }
+
+    /// xors seed into the key and permutes it, so the stream from the current
+    /// position on depends on both the old and new seed. This is what the
+    /// saved reseed count counts, thread_rng calls it with fresh OS entropy
+    /// periodically and after a fork.
+    pub fn reseed(&mut self, seed: &BitStream) {
+        let len = 25 * self.w;
+        let mut input = BitStream::new(len);
+        for idx in 0..len {
+            let bit = if idx < seed.len() { seed.get(idx) } else { 0 };
+            input.set(idx, self.key.get(idx) ^ bit);
+        }
+        let mut k = Keccak::new_sized(&input, self.w);
+        k.keccak(self.rounds);
+        self.key = k.get_state();
+        self.reseeds += 1;
+        self.block = None;
+    }
+
+    /// serializes everything needed to continue the stream: version 1 is
+    /// "KCTR", version, depth, rounds, position and reseed count (big-endian
+    /// u64s), then the key bits packed MSB-first into 25 * depth / 8 bytes.
+    pub fn save(&self) -> Vec<u8> {
+        let mut temp = Vec::with_capacity(HEADER_LEN + 25 * self.w / 8);
+        temp.extend_from_slice(MAGIC);
+        temp.push(VERSION);
+        temp.push(self.w as u8);
+        temp.push(self.rounds as u8);
+        temp.extend_from_slice(&self.position.to_be_bytes());
+        temp.extend_from_slice(&self.reseeds.to_be_bytes());
+        let mut key = self.key.to_bytes();
+        temp.extend_from_slice(&key);
+        wipe_vec_u8(&mut key);
+        return temp;
+    }
+
+    /// inverse of save, fails with Error::InvalidFormat if bytes weren't
+    /// produced by save or were written by an unknown version.
+    pub fn restore(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC || bytes[4] != VERSION {
+            return Err(Error::InvalidFormat);
+        }
+        let w = bytes[5] as usize;
+        let rounds = bytes[6] as u64;
+        if !w.is_power_of_two()
+            || !(8..=64).contains(&w)
+            || rounds == 0
+            || rounds > 12 + 2 * w.trailing_zeros() as u64
+            || bytes.len() != HEADER_LEN + 25 * w / 8
+        {
+            return Err(Error::InvalidFormat);
+        }
+        let mut word = [0_u8; 8];
+        word.copy_from_slice(&bytes[7..15]);
+        let position = u64::from_be_bytes(word);
+        word.copy_from_slice(&bytes[15..23]);
+        let reseeds = u64::from_be_bytes(word);
+        let mut temp = KeccakCtr::try_new(&BitStream::from_bytes(&bytes[HEADER_LEN..]), w, rounds)?;
+        temp.position = position;
+        temp.reseeds = reseeds;
+        return Ok(temp);
+    }
+
+    /// draws a value of any type implementing Random
+    pub fn gen<T: Random>(&mut self) -> T {
+        return T::random(self);
+    }
+
+    /// fills dest with successive values from the stream
+    pub fn fill(&mut self, dest: &mut [u64]) {
+        for val in dest.iter_mut() {
+            *val = self.next_u64();
+        }
+    }
+
+    /// overwrites buffer in place, so earlier output isn't left behind in a
+    /// freed allocation
+    fn fill_block(&mut self, counter: u64) {
+        let len = 25 * self.w;
+        let mut input = BitStream::new(len);
+        for idx in 0..len - 64 {
+            input.set(idx, self.key.get(idx));
+        }
+        for idx in 0..64 {
+            let bit = (counter >> (63 - idx)) as u8 & 1;
+            input.set(len - 64 + idx, self.key.get(len - 64 + idx) ^ bit);
+        }
+        let mut k = Keccak::new_sized(&input, self.w);
+        k.keccak(self.rounds);
+        let state = k.get_state();
+        self.buffer.resize(self.block_len(), 0);
+        for word in 0..self.block_len() {
+            let mut val: u64 = 0;
+            for idx in 0..64 {
+                val |= (state.get(64 * word + idx) as u64) << (63 - idx);
+            }
+            self.buffer[word] = val;
+        }
+    }
+}
+
+/// buffered output could be used to reconstruct values already handed out,
+/// so it is wiped before being freed, the key is wiped by BitStream's Drop
+impl Drop for KeccakCtr {
+    fn drop(&mut self) {
+        wipe_vec_u64(&mut self.buffer);
+    }
+}
+
+/// types that can be built from the output of a KeccakCtr
+pub trait Random: Sized {
+    fn random(rng: &mut KeccakCtr) -> Self;
+}
+
+macro_rules! impl_random_int {
+    ($($T:ty),*) => {
+        $(
+            impl Random for $T {
+                /// the most significant bits of the next u64
+                fn random(rng: &mut KeccakCtr) -> Self {
+                    return (rng.next_u64() >> (64 - <$T>::BITS)) as $T;
+                }
+            }
+        )*
+    };
+}
+
+impl_random_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl Random for u128 {
+    fn random(rng: &mut KeccakCtr) -> Self {
+        return ((rng.next_u64() as u128) << 64) | rng.next_u64() as u128;
+    }
+}
+
+impl Random for i128 {
+    fn random(rng: &mut KeccakCtr) -> Self {
+        return u128::random(rng) as i128;
+    }
+}
+
+impl Random for bool {
+    fn random(rng: &mut KeccakCtr) -> Self {
+        return rng.next_u64() >> 63 == 1;
+    }
+}
+
+impl Random for f64 {
+    /// uniform in [0, 1)
+    fn random(rng: &mut KeccakCtr) -> Self {
+        return (rng.next_u64() >> 11) as f64 / (1_u64 << 53) as f64;
+    }
+}
+
+impl Random for f32 {
+    /// uniform in [0, 1)
+    fn random(rng: &mut KeccakCtr) -> Self {
+        return (rng.next_u64() >> 40) as f32 / (1_u32 << 24) as f32;
+    }
+}
@@ -0,0 +1,91 @@
+use core::fmt::{self, Display, Formatter};
+
+/// everything the try_ functions can fail with. Their panicking
+/// counterparts panic with this error's message. New variants may be added
+/// without a major version bump.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// index wasn't less than the length of the bits or state
+    OutOfBounds { index: usize, len: usize },
+    /// bytes can only be appended when the length is a multiple of 8 bits
+    Unaligned { len: usize },
+    /// a slice was the wrong length for the bit length it should hold
+    Length { expected: usize, actual: usize },
+    /// two streams combined bit by bit had different bit lengths
+    Mismatch { left: usize, right: usize },
+    /// text couldn't be parsed, index is the byte offset of the first
+    /// character that didn't fit the format, or the length if it ended early
+    Parse { index: usize },
+    /// a source value didn't fit in a u64
+    Conversion,
+    /// more rounds were requested than the permutation has
+    TooManyRounds { rounds: u64, max: u64 },
+    /// fewer rounds were requested than the function needs to be safe
+    TooFewRounds { rounds: u64, min: u64 },
+    /// lane depth isn't supported, see the function's docs for which are
+    InvalidDepth { depth: usize },
+    /// permutation width isn't 25 times a power of two up to 1600
+    InvalidWidth { width: usize },
+    /// rate must be non-zero and leave a non-zero capacity
+    InvalidRate { rate: usize },
+    /// bytes weren't produced by the matching save function, or were
+    /// written by an unknown version of it
+    InvalidFormat,
+    /// a statistical test got less input than it can be computed from
+    TooShort { len: usize, min: usize },
+    /// a statistical test's block length is zero or too long for the input
+    InvalidBlockLength { len: usize },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        return match self {
+            Error::OutOfBounds { index, len } => {
+                write!(f, "index {} out of bounds for length {}", index, len)
+            }
+            Error::Unaligned { len } => {
+                write!(
+                    f,
+                    "can't append bytes at bit length {}, not a multiple of 8",
+                    len
+                )
+            }
+            Error::Length { expected, actual } => {
+                write!(f, "expected {} bytes, got {}", expected, actual)
+            }
+            Error::Mismatch { left, right } => {
+                write!(f, "bit lengths {} and {} don't match", left, right)
+            }
+            Error::Parse { index } => write!(f, "unexpected character at index {}", index),
+            Error::Conversion => write!(f, "value doesn't fit in a u64"),
+            Error::TooManyRounds { rounds, max } => {
+                write!(
+                    f,
+                    "{} rounds requested, the permutation has {}",
+                    rounds, max
+                )
+            }
+            Error::TooFewRounds { rounds, min } => {
+                write!(f, "{} rounds requested, at least {} needed", rounds, min)
+            }
+            Error::InvalidDepth { depth } => write!(f, "unsupported lane depth {}", depth),
+            Error::InvalidWidth { width } => write!(f, "unsupported permutation width {}", width),
+            Error::InvalidRate { rate } => write!(f, "unsupported rate {}", rate),
+            Error::InvalidFormat => write!(f, "unrecognized saved state"),
+            Error::TooShort { len, min } => {
+                write!(
+                    f,
+                    "input length {} is too short, the test needs {}",
+                    len, min
+                )
+            }
+            Error::InvalidBlockLength { len } => {
+                write!(f, "block length {} doesn't fit the input", len)
+            }
+        };
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
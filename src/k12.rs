@@ -0,0 +1,136 @@
+//! KangarooTwelve, KT128 in RFC 9861. TurboSHAKE128, a SHAKE128 style sponge
+//! over 12 rounds of Keccak-p[1600], hashes the input in 8192 byte chunks:
+//! the first chunk goes straight into the final node and every later one
+//! into a leaf whose 32 byte chaining value is absorbed after it. Needs no
+//! allocation.
+
+use crate::sponge::Sponge;
+
+const CHUNK: usize = 8192;
+const RATE: usize = 168;
+const ROUNDS: usize = 12;
+
+/// delimited suffixes of the TurboSHAKE128 calls, for input that fits in
+/// one chunk, for a leaf and for the final node over leaves
+const SINGLE_SUFFIX: u8 = 0x07;
+const LEAF_SUFFIX: u8 = 0x0B;
+const FINAL_SUFFIX: u8 = 0x06;
+
+/// big-endian x without leading zero bytes followed by their count, the
+/// bytes are the first .1 of .0
+fn length_encode(x: u64) -> ([u8; 9], usize) {
+    let len = 8 - x.leading_zeros() as usize / 8;
+    let mut temp = [0; 9];
+    temp[..len].copy_from_slice(&x.to_be_bytes()[8 - len..]);
+    temp[len] = len as u8;
+    return (temp, len + 1);
+}
+
+/// incremental KangarooTwelve with the same absorb, finish and squeeze
+/// steps as Sponge. The customization string is given to finish.
+#[derive(Clone, Debug)]
+pub struct KangarooTwelve {
+    node: Sponge<u64>,
+    leaf: Sponge<u64>,
+    /// chunks before the current one, so leaves absorbed when non zero
+    chunks: u64,
+    chunk_len: usize,
+    squeezing: bool,
+}
+
+impl Default for KangarooTwelve {
+    fn default() -> Self {
+        return KangarooTwelve::new();
+    }
+}
+
+impl KangarooTwelve {
+    pub fn new() -> Self {
+        return KangarooTwelve {
+            node: Sponge::with_rounds(RATE, ROUNDS),
+            leaf: Sponge::with_rounds(RATE, ROUNDS),
+            chunks: 0,
+            chunk_len: 0,
+            squeezing: false,
+        };
+    }
+
+    pub fn absorb(&mut self, input: &[u8]) {
+        assert!(!self.squeezing, "can't absorb after squeezing has started");
+        self.feed(input);
+    }
+
+    /// a chunk is only closed once more input arrives, as input of exactly
+    /// one chunk is hashed without a tree
+    fn feed(&mut self, mut input: &[u8]) {
+        while !input.is_empty() {
+            if self.chunk_len == CHUNK {
+                if self.chunks == 0 {
+                    self.node.absorb(&[0x03, 0, 0, 0, 0, 0, 0, 0]);
+                } else {
+                    self.end_leaf();
+                }
+                self.chunks += 1;
+                self.chunk_len = 0;
+            }
+            let take = (CHUNK - self.chunk_len).min(input.len());
+            if self.chunks == 0 {
+                self.node.absorb(&input[..take]);
+            } else {
+                self.leaf.absorb(&input[..take]);
+            }
+            self.chunk_len += take;
+            input = &input[take..];
+        }
+    }
+
+    fn end_leaf(&mut self) {
+        let mut chaining = [0; 32];
+        self.leaf.finish(LEAF_SUFFIX);
+        self.leaf.squeeze(&mut chaining);
+        self.node.absorb(&chaining);
+        self.leaf.reset();
+    }
+
+    /// closes the input with the customization string, empty for plain K12
+    pub fn finish(&mut self, customization: &[u8]) {
+        assert!(!self.squeezing, "KangarooTwelve has already been finished");
+        self.feed(customization);
+        let (len, used) = length_encode(customization.len() as u64);
+        self.feed(&len[..used]);
+        if self.chunks == 0 {
+            self.node.finish(SINGLE_SUFFIX);
+        } else {
+            self.end_leaf();
+            let (leaves, used) = length_encode(self.chunks);
+            self.node.absorb(&leaves[..used]);
+            self.node.absorb(&[0xFF, 0xFF]);
+            self.node.finish(FINAL_SUFFIX);
+        }
+        self.squeezing = true;
+    }
+
+    /// fills dest with output, can be called repeatedly for more
+    pub fn squeeze(&mut self, dest: &mut [u8]) {
+        assert!(self.squeezing, "finish must be called before squeezing");
+        self.node.squeeze(dest);
+    }
+
+    /// back to the empty state, wiping the old one
+    pub fn reset(&mut self) {
+        self.node.reset();
+        self.leaf.reset();
+        self.chunks = 0;
+        self.chunk_len = 0;
+        self.squeezing = false;
+    }
+}
+
+/// fills dest with KangarooTwelve output for input and the customization
+/// string, any length
+pub fn k12(input: &[u8], customization: &[u8], dest: &mut [u8]) {
+    let mut k = KangarooTwelve::new();
+    k.absorb(input);
+    k.finish(customization);
+    k.squeeze(dest);
+}
@@ -0,0 +1,282 @@
+//! array backed Keccak-f[25 * w] for lane widths w of 8, 16, 32 and 64 bits.
+//! Each lane is stored in an integer, bit z of lane (x, y) is bit z of
+//! lanes[5 * y + x], so the whole state lives inline with no allocation.
+//! Bytes map onto lanes little-endian, as in FIPS 202.
+
+use crate::{wipe::wipe_bytes, Error};
+use core::{
+    fmt::Debug,
+    mem::size_of_val,
+    ops::{BitAnd, BitXor, BitXorAssign, Not},
+};
+
+/// integer types usable as a lane
+pub trait Lane:
+    Copy
+    + Default
+    + Debug
+    + PartialEq
+    + BitXor<Output = Self>
+    + BitXorAssign
+    + BitAnd<Output = Self>
+    + Not<Output = Self>
+{
+    /// lane width w
+    const BITS: u32;
+    /// log base 2 of w
+    const L: u32;
+    fn rotate_left(self, n: u32) -> Self;
+    /// the low w bits of val
+    fn truncate(val: u64) -> Self;
+    fn to_u64(self) -> u64;
+}
+
+macro_rules! impl_lane {
+    ($($T:ty),*) => {
+        $(
+            impl Lane for $T {
+                const BITS: u32 = <$T>::BITS;
+                const L: u32 = <$T>::BITS.trailing_zeros();
+
+                #[inline]
+                fn rotate_left(self, n: u32) -> Self {
+                    return <$T>::rotate_left(self, n);
+                }
+
+                #[inline]
+                fn truncate(val: u64) -> Self {
+                    return val as $T;
+                }
+
+                #[inline]
+                fn to_u64(self) -> u64 {
+                    return self as u64;
+                }
+            }
+        )*
+    };
+}
+
+impl_lane!(u8, u16, u32, u64);
+
+/// iota's round constants for keccak-f[1600], a narrower lane uses the low
+/// w bits of the same constants
+const RC: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808A,
+    0x8000000080008000,
+    0x000000000000808B,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008A,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000A,
+    0x000000008000808B,
+    0x800000000000008B,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800A,
+    0x800000008000000A,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// rho's rotation offsets indexed by 5 * y + x, reduced mod w when applied
+const RHO: [u32; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+#[derive(Clone, PartialEq)]
+pub struct KeccakF<L: Lane> {
+    lanes: [L; 25],
+}
+
+pub type KeccakF200 = KeccakF<u8>;
+pub type KeccakF400 = KeccakF<u16>;
+pub type KeccakF800 = KeccakF<u32>;
+pub type KeccakF1600 = KeccakF<u64>;
+
+impl<L: Lane> Default for KeccakF<L> {
+    fn default() -> Self {
+        return KeccakF::new();
+    }
+}
+
+impl<L: Lane> KeccakF<L> {
+    /// number of rounds in the full permutation, 12 + 2l
+    pub const ROUNDS: usize = 12 + 2 * L::L as usize;
+    /// state size in bytes
+    pub const BYTES: usize = 25 * L::BITS as usize / 8;
+
+    /// all zero state
+    pub fn new() -> Self {
+        return KeccakF {
+            lanes: [L::default(); 25],
+        };
+    }
+
+    pub fn from_lanes(lanes: [L; 25]) -> Self {
+        return KeccakF { lanes };
+    }
+
+    /// lane (x, y) is at index 5 * y + x
+    pub fn lanes(&self) -> &[L; 25] {
+        return &self.lanes;
+    }
+
+    pub fn lanes_mut(&mut self) -> &mut [L; 25] {
+        return &mut self.lanes;
+    }
+
+    pub fn lane(&self, x: usize, y: usize) -> L {
+        return self.lanes[5 * y + x];
+    }
+
+    /// byte idx of the state, lanes in order, each lane little-endian
+    pub fn byte(&self, idx: usize) -> u8 {
+        let width = L::BITS as usize / 8;
+        return (self.lanes[idx / width].to_u64() >> (8 * (idx % width))) as u8;
+    }
+
+    /// xors bytes into the state starting at byte offset
+    pub fn xor_bytes(&mut self, offset: usize, bytes: &[u8]) {
+        assert!(
+            offset + bytes.len() <= Self::BYTES,
+            "bytes don't fit in the state"
+        );
+        let width = L::BITS as usize / 8;
+        for (idx, &byte) in bytes.iter().enumerate() {
+            let pos = offset + idx;
+            self.lanes[pos / width] ^= L::truncate((byte as u64) << (8 * (pos % width)));
+        }
+    }
+
+    /// copies state bytes starting at offset into dest
+    pub fn extract_bytes(&self, offset: usize, dest: &mut [u8]) {
+        assert!(
+            offset + dest.len() <= Self::BYTES,
+            "dest is larger than the state"
+        );
+        for (idx, byte) in dest.iter_mut().enumerate() {
+            *byte = self.byte(offset + idx);
+        }
+    }
+
+    pub fn theta(&mut self) {
+        let mut c = [L::default(); 5];
+        for (x, parity) in c.iter_mut().enumerate() {
+            *parity = self.lanes[x]
+                ^ self.lanes[x + 5]
+                ^ self.lanes[x + 10]
+                ^ self.lanes[x + 15]
+                ^ self.lanes[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                self.lanes[5 * y + x] ^= d;
+            }
+        }
+    }
+
+    pub fn rho(&mut self) {
+        for (lane, &offset) in self.lanes.iter_mut().zip(RHO.iter()) {
+            *lane = lane.rotate_left(offset % L::BITS);
+        }
+    }
+
+    /// lane (x, y) moves to (y, 2x + 3y)
+    pub fn pi(&mut self) {
+        let old = self.lanes;
+        for x in 0..5 {
+            for y in 0..5 {
+                self.lanes[5 * ((2 * x + 3 * y) % 5) + y] = old[5 * y + x];
+            }
+        }
+    }
+
+    pub fn chi(&mut self) {
+        for y in 0..5 {
+            let row = [
+                self.lanes[5 * y],
+                self.lanes[5 * y + 1],
+                self.lanes[5 * y + 2],
+                self.lanes[5 * y + 3],
+                self.lanes[5 * y + 4],
+            ];
+            for x in 0..5 {
+                self.lanes[5 * y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+    }
+
+    /// round is the round index ir, 0 <= ir < ROUNDS
+    pub fn iota(&mut self, round: usize) {
+        self.lanes[0] ^= L::truncate(RC[round]);
+    }
+
+    /// all five step mappings with round index ir
+    pub fn round(&mut self, round: usize) {
+        self.theta();
+        self.rho();
+        self.pi();
+        self.chi();
+        self.iota(round);
+    }
+
+    /// the full Keccak-f permutation
+    pub fn permute(&mut self) {
+        self.permute_rounds(Self::ROUNDS);
+    }
+
+    /// Keccak-p with num_rounds rounds, ie. the last num_rounds rounds of
+    /// Keccak-f
+    pub fn permute_rounds(&mut self, num_rounds: usize) {
+        if let Err(err) = self.try_permute_rounds(num_rounds) {
+            panic!("keccak-f[{}]: {}", 25 * L::BITS, err);
+        }
+    }
+
+    pub fn try_permute_rounds(&mut self, num_rounds: usize) -> Result<(), Error> {
+        if num_rounds > Self::ROUNDS {
+            return Err(Error::TooManyRounds {
+                rounds: num_rounds as u64,
+                max: Self::ROUNDS as u64,
+            });
+        }
+        for round in (Self::ROUNDS - num_rounds)..Self::ROUNDS {
+            self.round(round);
+        }
+        return Ok(());
+    }
+
+    /// sets every lane to zero with writes that won't be optimized away
+    pub fn reset(&mut self) {
+        unsafe {
+            wipe_bytes(self.lanes.as_mut_ptr() as *mut u8, size_of_val(&self.lanes));
+        }
+    }
+}
+
+/// state may be key material, wiped when it goes out of scope
+impl<L: Lane> Drop for KeccakF<L> {
+    fn drop(&mut self) {
+        self.reset();
+    }
+}
+
+impl<L: Lane> Debug for KeccakF<L> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        return f
+            .debug_struct("KeccakF")
+            .field("lanes", &self.lanes)
+            .finish();
+    }
+}
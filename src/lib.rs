@@ -1,51 +1,128 @@
+//! Without the default `std` feature the crate is `no_std`. The array backed
+//! KeccakF permutations and the Sponge over them never allocate, BitStream,
+//! Keccak and KeccakCtr need the `alloc` feature, and thread_rng needs `std`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
 pub mod bit_stream;
+#[cfg(feature = "alloc")]
+pub mod ctr;
+mod error;
+pub mod k12;
+pub mod keccak_f;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod sha3;
+pub mod sponge;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "alloc")]
+mod text;
+#[cfg(feature = "std")]
+pub mod thread_rng;
+#[cfg(feature = "alloc")]
 mod vla;
+mod wipe;
 
-use std::{
-    cmp::min,
-    fmt::{Debug, Display, Error, Formatter},
-    vec::Vec,
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::{
+    fmt::{self, Debug, Display, Formatter},
+    mem,
 };
 
-pub use bit_stream::BitStream;
+#[cfg(feature = "alloc")]
+pub use bit_stream::{BitOrder, BitSlice, BitStream, Endian};
+#[cfg(feature = "alloc")]
+pub use ctr::{KeccakCtr, Random};
+pub use error::Error;
+pub use k12::{k12, KangarooTwelve};
+pub use keccak_f::{KeccakF, KeccakF1600, KeccakF200, KeccakF400, KeccakF800};
+pub use sha3::{sha3_224, sha3_256, sha3_384, sha3_512, shake128, shake256};
+pub use sponge::Sponge;
+#[cfg(feature = "std")]
+pub use thread_rng::{random, thread_rng, ThreadRng};
+#[cfg(feature = "alloc")]
 use vla::VLA;
 
+#[cfg(feature = "alloc")]
 type Bit = u8;
 
-pub struct Keccak<'a> {
-    state: &'a mut VLA<Bit>,
-    state1: &'a mut VLA<Bit>,
-    w: usize, //depth, or length of each lane
-    l: u64,   //log base 2 of w
+/// copy of a Keccak state that can outlive it, see Keccak::snapshot.
+/// With the `serde` feature the state bits serialize as hex in human-readable
+/// formats, and deserializing checks the depth and state length the same way
+/// Keccak::try_from_snapshot does.
+#[cfg(feature = "alloc")]
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "serialize::RawSnapshot"))]
+pub struct KeccakSnapshot {
+    pub depth: usize,
+    pub state: BitStream,
 }
 
-macro_rules! mut_ref_swap {
-    ($first:expr, $second:expr, $T:ty) => {
-        unsafe {
-            let ptr1: *mut $T = ($first);
-            $first = &mut *($second as *mut $T);
-            $second = &mut *ptr1;
+#[cfg(feature = "alloc")]
+impl KeccakSnapshot {
+    /// the depth must be one of 1, 2, 4 ... 64 and the state 25 * depth
+    /// bits long
+    fn check(&self) -> Result<(), Error> {
+        let width = Keccak::check_depth(self.depth)?;
+        if self.state.len() != width {
+            return Err(Error::InvalidWidth {
+                width: self.state.len(),
+            });
         }
-    };
+        return Ok(());
+    }
 }
 
-impl Keccak<'_> {
-    /// 0<=x<=width, 0<=y<=height, 0<=z<=depth
-    /// gets from state
+/// first bytes of every saved Keccak, followed by the format version
+#[cfg(feature = "alloc")]
+const MAGIC: &[u8; 4] = b"KECC";
+#[cfg(feature = "alloc")]
+const VERSION: u8 = 1;
+/// magic, version and depth
+#[cfg(feature = "alloc")]
+const HEADER_LEN: usize = 4 + 1 + 1;
+
+/// each step mapping reads state and writes state1, then the two are swapped
+#[cfg(feature = "alloc")]
+#[derive(Clone)]
+pub struct Keccak {
+    state: VLA<Bit>,
+    state1: VLA<Bit>,
+    w: usize, //depth, or length of each lane
+    l: u64,   //log base 2 of w
+}
+
+#[cfg(feature = "alloc")]
+impl Keccak {
+    /// 0<=x<width, 0<=y<height, 0<=z<depth
+    /// gets from state. Both buffers hold 25 * w bits, so any coordinates in
+    /// range index inside them and the bounds check can be skipped. The step
+    /// mappings only loop over coordinates in range, the public lane
+    /// accessors assert x and y before calling get, set or set_state.
     #[inline]
     fn get(&self, x: usize, y: usize, z: usize) -> Bit {
-        return self.state.get(self.w * (5 * y + x) + z);
+        debug_assert!(x < 5 && y < 5 && z < self.w);
+        return unsafe { self.state.get_unchecked(self.w * (5 * y + x) + z) };
     }
 
     /// sets state1
     #[inline]
     fn set(&mut self, x: usize, y: usize, z: usize, val: Bit) {
-        self.state1.set(self.w * (5 * y + x) + z, val);
+        debug_assert!(x < 5 && y < 5 && z < self.w);
+        unsafe { self.state1.set_unchecked(self.w * (5 * y + x) + z, val) };
     }
 
     #[inline]
     fn set_state(&mut self, x: usize, y: usize, z: usize, val: Bit) {
-        self.state.set(self.w * (5 * y + x) + z, val);
+        debug_assert!(x < 5 && y < 5 && z < self.w);
+        unsafe { self.state.set_unchecked(self.w * (5 * y + x) + z, val) };
     }
 
     #[inline]
@@ -53,7 +130,15 @@ impl Keccak<'_> {
         return self.w * (5 * y + x) + z;
     }
 
+    /// bits of lane (x, y), bit z at index z. Panics unless x and y are
+    /// below 5, as get skips the bounds check.
     pub fn get_lane(&self, x: usize, y: usize) -> Vec<Bit> {
+        assert!(
+            x < 5 && y < 5,
+            "lane ({}, {}) is outside the 5x5 state",
+            x,
+            y
+        );
         let mut temp = Vec::new();
         for z in 0..self.w {
             temp.push(self.get(x, y, z));
@@ -61,55 +146,235 @@ impl Keccak<'_> {
         return temp;
     }
 
+    /// lane (x, y) packed with bit z of the lane in bit z of the result, as
+    /// KeccakF stores its lanes. Bits above the depth are zero.
+    pub fn lane(&self, x: usize, y: usize) -> u64 {
+        assert!(
+            x < 5 && y < 5,
+            "lane ({}, {}) is outside the 5x5 state",
+            x,
+            y
+        );
+        assert!(self.w <= 64, "lanes deeper than 64 bits don't fit in a u64");
+        let mut temp: u64 = 0;
+        for z in 0..self.w {
+            temp |= (self.get(x, y, z) as u64) << z;
+        }
+        return temp;
+    }
+
     pub fn depth(&self) -> usize {
         return self.w;
     }
 
     /// state arrays depth initialized to message length / 25.
     /// If the message is not evenly divisble by 25 then the message size will
-    /// be rounded down to the nearest multiple of 25, if message contains more
-    /// bits than state then some bits in message are unused
+    /// be rounded down to the nearest multiple of 25. Panics unless the
+    /// depth is 1, 2, 4 ... 64, see try_new.
     pub fn new(message: &BitStream) -> Self {
-        unsafe {
-            let mut temp = Keccak {
-                state: &mut *(Box::into_raw(Box::from(VLA::new((message.len() / 25) * 25)))),
-                state1: &mut *(Box::into_raw(Box::from(VLA::new((message.len() / 25) * 25)))),
-                w: message.len() / 25,
-                l: 0,
-            };
-            temp.l = f64::log2(temp.w as f64) as u64; //w should equal 2^(integer)
-            for idx in 0..min(message.len(), temp.w * 25) {
-                temp.state.set(idx, message[idx]);
-            }
-            return temp;
-        }
+        return match Keccak::try_new(message) {
+            Ok(k) => k,
+            Err(err) => panic!("{}", err),
+        };
+    }
+
+    /// like new, but a depth other than 1, 2, 4 ... 64 is an error instead
+    /// of a panic
+    pub fn try_new(message: &BitStream) -> Result<Self, Error> {
+        return Keccak::try_new_sized(message, message.len() / 25);
     }
 
     /// behaves like new, except the state depth is set to size,
     /// if message contains fewer bit then the state, the remaining bits are
-    /// zero-initialized.
+    /// zero-initialized, if it contains more then some bits are unused.
+    /// Panics unless size is 1, 2, 4 ... 64, see try_new_sized.
     pub fn new_sized(message: &BitStream, size: usize) -> Self {
-        unsafe {
-            let mut temp = Keccak {
-                state: &mut *(Box::into_raw(Box::from(VLA::new(size * 25)))),
-                state1: &mut *(Box::into_raw(Box::from(VLA::new(size * 25)))),
-                w: size,
-                l: 0,
-            };
-            temp.l = f64::log2(temp.w as f64) as u64;
-            for idx in 0..min(message.len(), temp.w * 25) {
-                temp.state.set(idx, message[idx]);
+        return match Keccak::try_new_sized(message, size) {
+            Ok(k) => k,
+            Err(err) => panic!("{}", err),
+        };
+    }
+
+    /// like new_sized, but a size other than 1, 2, 4 ... 64 is an error
+    /// instead of a panic
+    pub fn try_new_sized(message: &BitStream, size: usize) -> Result<Self, Error> {
+        let width = Keccak::check_depth(size)?;
+        let mut temp = Keccak {
+            state: VLA::new(width),
+            state1: VLA::new(width),
+            w: size,
+            l: size.trailing_zeros() as u64,
+        };
+        for (bit, val) in temp.state.iter_mut().zip(message) {
+            *bit = val;
+        }
+        return Ok(temp);
+    }
+
+    /// all zero state of width bits, 25 times a depth of 1, 2, 4 ... 64.
+    /// Panics on any other width.
+    pub fn zeroed(width: usize) -> Self {
+        return match Keccak::check_width(width) {
+            Ok(depth) => Keccak::new_sized(&BitStream::new(0), depth),
+            Err(err) => panic!("{}", err),
+        };
+    }
+
+    /// keccak-f[1600] state holding lanes, lane (x, y) at index 5 * y + x
+    /// with bit z of the lane in bit z of the u64
+    pub fn from_lanes(lanes: &[u64; 25]) -> Self {
+        let mut temp = Keccak::new_sized(&BitStream::new(0), 64);
+        for (idx, &lane) in lanes.iter().enumerate() {
+            temp.set_lane(idx % 5, idx / 5, lane);
+        }
+        return temp;
+    }
+
+    /// state of width bits read from bytes the way FIPS 202 maps bytes to
+    /// states, lane by lane, each lane little-endian and each byte
+    /// LSB-first. Fewer bytes than the state leave the rest zero.
+    pub fn from_bytes(bytes: &[u8], width: usize) -> Result<Self, Error> {
+        let depth = Keccak::check_width(width)?;
+        let mut temp = Keccak::new_sized(&BitStream::new(0), depth);
+        temp.xor_bytes_into_state(0, bytes)?;
+        return Ok(temp);
+    }
+
+    /// sets lane (x, y) to the low depth bits of val, bit z of the lane
+    /// from bit z of val
+    pub fn set_lane(&mut self, x: usize, y: usize, val: u64) {
+        self.check_lane(x, y);
+        for z in 0..self.w {
+            self.set_state(x, y, z, (val >> z) as Bit & 1);
+        }
+    }
+
+    /// xors the low depth bits of val into lane (x, y)
+    pub fn xor_lane(&mut self, x: usize, y: usize, val: u64) {
+        self.check_lane(x, y);
+        for z in 0..self.w {
+            self.set_state(x, y, z, self.get(x, y, z) ^ ((val >> z) as Bit & 1));
+        }
+    }
+
+    /// xors bytes into the state starting at byte offset, mapped as in
+    /// from_bytes. Fails without changing the state if they don't fit.
+    pub fn xor_bytes_into_state(&mut self, offset: usize, bytes: &[u8]) -> Result<(), Error> {
+        let end = offset.saturating_add(bytes.len());
+        if end > (25 * self.w).div_ceil(8) {
+            return Err(Error::OutOfBounds {
+                index: end,
+                len: (25 * self.w).div_ceil(8),
+            });
+        }
+        let width = 25 * self.w;
+        for (idx, &byte) in bytes.iter().enumerate() {
+            let start = 8 * (offset + idx);
+            for bit in 0..8.min(width - start) {
+                let old = self.state.get(start + bit);
+                self.state.set(start + bit, old ^ ((byte >> bit) & 1));
             }
-            return temp;
         }
+        return Ok(());
+    }
+
+    /// the depth of a state of width bits
+    fn check_width(width: usize) -> Result<usize, Error> {
+        let depth = width / 25;
+        if !width.is_multiple_of(25) || !depth.is_power_of_two() || depth > 64 {
+            return Err(Error::InvalidWidth { width });
+        }
+        return Ok(depth);
+    }
+
+    /// the width of a state of depth bits, checked like check_width
+    fn check_depth(depth: usize) -> Result<usize, Error> {
+        let width = match depth.checked_mul(25) {
+            Some(width) => width,
+            None => return Err(Error::InvalidWidth { width: usize::MAX }),
+        };
+        Keccak::check_width(width)?;
+        return Ok(width);
+    }
+
+    fn check_lane(&self, x: usize, y: usize) {
+        assert!(
+            x < 5 && y < 5,
+            "lane ({}, {}) is outside the 5x5 state",
+            x,
+            y
+        );
+        assert!(self.w <= 64, "lanes deeper than 64 bits don't fit in a u64");
     }
 
     pub fn get_state(&self) -> BitStream {
-        let mut tmp = BitStream::new(self.w * 25);
-        for idx in 0..(25 * self.w) {
-            tmp.set(idx, self.state.get(idx));
+        return self.state.iter().copied().collect();
+    }
+
+    /// zeroes the state, keeping the depth. Freed state is also zeroed on
+    /// drop, so secrets don't outlive the Keccak either way.
+    pub fn reset(&mut self) {
+        self.state.zeroize();
+        self.state1.zeroize();
+    }
+
+    pub fn snapshot(&self) -> KeccakSnapshot {
+        return KeccakSnapshot {
+            depth: self.w,
+            state: self.get_state(),
+        };
+    }
+
+    /// rebuilds the state saved by snapshot. Panics if the snapshot's depth
+    /// or state length is invalid, see try_from_snapshot.
+    pub fn from_snapshot(snapshot: &KeccakSnapshot) -> Self {
+        return match Keccak::try_from_snapshot(snapshot) {
+            Ok(k) => k,
+            Err(err) => panic!("{}", err),
+        };
+    }
+
+    /// like from_snapshot, but a depth other than 1, 2, 4 ... 64 or a state
+    /// that isn't 25 * depth bits long is an error instead of a panic
+    pub fn try_from_snapshot(snapshot: &KeccakSnapshot) -> Result<Self, Error> {
+        snapshot.check()?;
+        return Ok(Keccak::new_sized(&snapshot.state, snapshot.depth));
+    }
+
+    /// serializes the state with its depth: version 1 is "KECC", version
+    /// and depth, then the state bits packed MSB-first into
+    /// ceil(25 * depth / 8) bytes, the unused bits of the last byte zero.
+    pub fn save(&self) -> Vec<u8> {
+        let mut temp = Vec::with_capacity(HEADER_LEN + (25 * self.w).div_ceil(8));
+        temp.extend_from_slice(MAGIC);
+        temp.push(VERSION);
+        temp.push(self.w as u8);
+        let mut state = self.get_state().to_bytes();
+        temp.extend_from_slice(&state);
+        wipe::wipe_vec_u8(&mut state);
+        return temp;
+    }
+
+    /// inverse of save. The depth is checked like try_from_snapshot does and
+    /// fails with Error::InvalidWidth, anything else that save wouldn't have
+    /// written fails with Error::InvalidFormat.
+    pub fn restore(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC || bytes[4] != VERSION {
+            return Err(Error::InvalidFormat);
+        }
+        let depth = bytes[5] as usize;
+        let width = 25 * depth;
+        Keccak::check_width(width)?;
+        let state = match BitStream::from_partial_bytes(&bytes[HEADER_LEN..], width) {
+            Ok(state) => state,
+            Err(_) => return Err(Error::InvalidFormat),
+        };
+        // padding bits are dropped by from_partial_bytes, so a non zero one
+        // would give a second encoding of the same state
+        if !width.is_multiple_of(8) && bytes[bytes.len() - 1] << (width % 8) != 0 {
+            return Err(Error::InvalidFormat);
         }
-        return tmp;
+        return Keccak::try_from_snapshot(&KeccakSnapshot { depth, state });
     }
 
     /// copies and returns the first 8 bytes of self.state
@@ -121,7 +386,7 @@ impl Keccak<'_> {
         return temp;
     }
 
-    fn column_parity<'a>(&'a self, x: usize, z: usize) -> Bit {
+    fn column_parity(&self, x: usize, z: usize) -> Bit {
         return self.get(x, 0, z)
             ^ self.get(x, 1, z)
             ^ self.get(x, 2, z)
@@ -129,68 +394,53 @@ impl Keccak<'_> {
             ^ self.get(x, 4, z);
     }
 
-    fn theta<'a>(&'a mut self) {
-        for x in 0..(5 as i64) {
-            for z in 0..(self.w as i64) {
-                let d = self.column_parity(((x - 1) % 5).abs() as usize, z as usize)
-                    ^ self.column_parity(
-                        (x + 1) as usize % 5,
-                        ((z - 1) % self.w as i64).abs() as usize,
-                    );
-                for y in 0..(5 as usize) {
-                    self.set(
-                        x as usize,
-                        y,
-                        z as usize,
-                        self.get(x as usize, y, z as usize) ^ d,
-                    );
+    /// x - 1 and z - 1 wrap around, adding the modulus keeps them unsigned
+    fn theta(&mut self) {
+        for x in 0..5_usize {
+            for z in 0..self.w {
+                let d = self.column_parity((x + 4) % 5, z)
+                    ^ self.column_parity((x + 1) % 5, (z + self.w - 1) % self.w);
+                for y in 0..5_usize {
+                    self.set(x, y, z, self.get(x, y, z) ^ d);
                 }
             }
         }
-        mut_ref_swap!(self.state, self.state1, VLA<Bit>);
+        mem::swap(&mut self.state, &mut self.state1);
     }
 
-    fn rho<'a>(&'a mut self) {
+    fn rho(&mut self) {
         for z in 0..self.w {
             self.set(0, 0, z, self.get(0, 0, z))
         }
         // could use lookup table to reduce calculations
         let (mut x, mut y) = (1, 0);
-        for t in 0..23 {
+        for t in 0..24 {
+            let offset = (t + 1) * (t + 2) / 2 % self.w;
             for z in 0..self.w {
-                self.set(
-                    x,
-                    y,
-                    z,
-                    self.get(
-                        x,
-                        y,
-                        (z as i64 - (t + 1) * (t + 2) / 2).abs() as usize % self.w,
-                    ),
-                );
+                self.set(x, y, z, self.get(x, y, (z + self.w - offset) % self.w));
             }
             let temp = y;
             y = (2 * x + 3 * y) % 5;
             x = temp;
         }
-        mut_ref_swap!(self.state, self.state1, VLA<Bit>);
+        mem::swap(&mut self.state, &mut self.state1);
     }
 
-    fn pi<'a>(&'a mut self) {
-        for x in 0..(5 as usize) {
-            for y in 0..(5 as usize) {
+    fn pi(&mut self) {
+        for x in 0..5_usize {
+            for y in 0..5_usize {
                 for z in 0..self.w {
                     self.state1
                         .set(self.get_idx(x, y, z), self.get((x + 3 * y) % 5, x, z));
                 }
             }
         }
-        mut_ref_swap!(self.state, self.state1, VLA<Bit>);
+        mem::swap(&mut self.state, &mut self.state1);
     }
 
-    fn chi<'a>(&'a mut self) {
-        for x in 0..(5 as usize) {
-            for y in 0..(5 as usize) {
+    fn chi(&mut self) {
+        for x in 0..5_usize {
+            for y in 0..5_usize {
                 for z in 0..self.w {
                     self.set(
                         x,
@@ -202,11 +452,11 @@ impl Keccak<'_> {
                 }
             }
         }
-        mut_ref_swap!(self.state, self.state1, VLA<Bit>);
+        mem::swap(&mut self.state, &mut self.state1);
     }
 
     fn rc(t: u64) -> Bit {
-        if t % 255 == 0 {
+        if t.is_multiple_of(255) {
             return 1;
         }
         let mut state: u32 = 1;
@@ -214,9 +464,9 @@ impl Keccak<'_> {
             state <<= 1;
             let eighth: u32 = (state & 0b100000000) >> 8;
             state ^= eighth;
-            state ^= eighth << 3;
             state ^= eighth << 4;
             state ^= eighth << 5;
+            state ^= eighth << 6;
             state &= 0xFF;
         }
         return (state as u8) & 1;
@@ -224,65 +474,72 @@ impl Keccak<'_> {
 
     fn iota(&mut self, round: u64) {
         let mut rc = BitStream::new(self.w);
-        for j in 0..self.l {
-            rc.set(
-                i64::pow(2, j as u32) as usize - 1,
-                Keccak::rc(j as u64 + 7 * round),
-            )
+        for j in 0..=self.l {
+            rc.set((1 << j) - 1, Keccak::rc(j + 7 * round))
         }
         for z in 0..self.w {
             self.set_state(0, 0, z, self.get(0, 0, z) ^ rc.get(z));
         }
     }
 
+    /// the last num_rounds rounds of the permutation, panics if there are
+    /// more than 12 + 2l
     pub fn keccak(&mut self, num_rounds: u64) {
-        for r in (12 + 2 * self.l - num_rounds as u64)..(12 + 2 * self.l) {
-            self.theta();
-            self.rho();
-            self.pi();
-            self.chi();
-            self.iota(r);
+        if let Err(err) = self.try_keccak(num_rounds) {
+            panic!("{}", err);
+        }
+    }
+
+    pub fn try_keccak(&mut self, num_rounds: u64) -> Result<(), Error> {
+        let max = 12 + 2 * self.l;
+        if num_rounds > max {
+            return Err(Error::TooManyRounds {
+                rounds: num_rounds,
+                max,
+            });
+        }
+        for r in (12 + 2 * self.l - num_rounds)..(12 + 2 * self.l) {
+            self.round(r);
         }
+        return Ok(());
+    }
+
+    fn round(&mut self, round: u64) {
+        self.theta();
+        self.rho();
+        self.pi();
+        self.chi();
+        self.iota(round);
     }
 
-    /// pads message so it may be split evenly into blocks,
+    /// pads message with zero bits so it may be split evenly into blocks,
     /// each block is xor'ed with the current state, and a round of keccak is done
     pub fn sponge_absorb(&mut self, message: &mut BitStream) {
-        if message.len() % (self.w * 25) != 0 {
-            let pad = (f64::ceil(message.len() as f64 / (self.w * 25) as f64) as u64
-                * (self.w * 25) as u64)
-                - message.len() as u64;
-            let mut temp = Vec::<u8>::new();
-            temp.resize(pad as usize, 0_u8);
-            message.add_val(temp.as_slice());
-        }
-        let block: usize = message.len() / (self.depth() * 25);
-        let mut count: usize = 0;
-        while count < message.len() {
-            for x in 0..5 as usize {
-                for y in 0..5 as usize {
-                    for z in 0..self.depth() {
-                        self.set_state(
-                            x,
-                            y,
-                            z,
-                            self.get(x, y, z) ^ message.get(self.depth() * (5 * y + x) + z),
-                        );
-                    }
-                }
+        let block = self.depth() * 25;
+        if block == 0 {
+            return;
+        }
+        if !message.len().is_multiple_of(block) {
+            let pad = message.len().div_ceil(block) * block - message.len();
+            message.extend_bits(core::iter::repeat_n(0, pad));
+        }
+        for start in (0..message.len()).step_by(block) {
+            let mixed = &self.get_state() ^ &message.slice(start..start + block).to_bit_stream();
+            for (bit, val) in self.state.iter_mut().zip(&mixed) {
+                *bit = val;
             }
             self.keccak(12 + 2 * self.l);
-            count += block;
         }
     }
 }
 
-impl Debug for Keccak<'_> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+#[cfg(feature = "alloc")]
+impl Debug for Keccak {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         for idx in 0..25 {
-            match write!(
+            match writeln!(
                 f,
-                "x:{} y:{}, {:?}\n",
+                "x:{} y:{}, {:?}",
                 idx % 5,
                 idx / 5,
                 self.get_lane(idx % 5, idx / 5)
@@ -296,30 +553,22 @@ impl Debug for Keccak<'_> {
 }
 
 /// allows use of ToString
-impl Display for Keccak<'_> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+#[cfg(feature = "alloc")]
+impl Display for Keccak {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         return Debug::fmt(self, f);
     }
 }
 
-impl Drop for Keccak<'_> {
-    fn drop(&mut self) {
-        unsafe {
-            let ptr1: *mut VLA<Bit> = self.state;
-            let ptr2: *mut VLA<Bit> = self.state1;
-            std::ptr::drop_in_place(ptr1);
-            std::ptr::drop_in_place(ptr2);
-        }
-    }
-}
-
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
 
-    use crate::bit_stream::BitStream;
+    use crate::bit_stream::{BitOrder, BitStream, Endian};
+    use crate::ctr::KeccakCtr;
+    use crate::keccak_f::Lane;
+    use crate::thread_rng::{random, thread_rng, ReseedingCtr};
     use crate::vla::VLA;
-    use crate::Keccak;
-    use std::mem::size_of;
+    use crate::{Error, Keccak, KeccakF, KeccakF1600, KeccakF200, KeccakF800, Sponge};
 
     #[test]
     fn test_vla() {
@@ -332,6 +581,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_vla_slices() {
+        let mut a = VLA::<u8>::new(4);
+        a.as_mut_slice().copy_from_slice(&[1, 2, 3, 4]);
+        for val in &mut a {
+            *val *= 2;
+        }
+        assert_eq!(a.as_slice(), &[2, 4, 6, 8]);
+        assert_eq!(a.iter().map(|&val| val as u32).sum::<u32>(), 20);
+        assert_eq!(a.try_get(3), Some(8));
+        assert_eq!(a.try_get(4), None);
+        assert_eq!(unsafe { a.get_unchecked(1) }, 4);
+        let copy = a.clone();
+        a.zeroize();
+        assert_eq!(a.as_slice(), &[0, 0, 0, 0]);
+        assert_eq!(copy.as_slice(), &[2, 4, 6, 8]);
+        assert_eq!(format!("{:?}", copy), "VLA { data: [2, 4, 6, 8] }");
+    }
+
+    #[test]
+    fn test_vla_empty() {
+        let mut empty = VLA::<u64>::new(0);
+        assert!(empty.is_empty());
+        assert_eq!(empty.try_get(0), None);
+        empty.zeroize();
+        assert_eq!(empty.clone(), empty);
+        let mut unit = VLA::<()>::new(3);
+        unit.set(2, ());
+        assert_eq!(unit.len(), 3);
+        assert_eq!(unit.iter().count(), 3);
+        drop(unit);
+        assert_eq!(
+            Keccak::try_new(&BitStream::from_u64(&[])).err(),
+            Some(Error::InvalidWidth { width: 0 })
+        );
+    }
+
     #[test]
     #[should_panic]
     fn test_vla_panic_get() {
@@ -352,6 +638,19 @@ mod tests {
         assert_eq!(bits.get(62), 0);
     }
 
+    #[test]
+    fn test_bitstream_set() {
+        let mut bits = BitStream::new(128);
+        for idx in [0, 3, 63, 64, 100] {
+            bits.set(idx, 1);
+        }
+        bits.set(3, 0);
+        assert_eq!(
+            bits.as_vec_u64()[..2],
+            [0x8000000000000001, 0x8000000008000000]
+        );
+    }
+
     #[test]
     fn test_bitstream_from_u64() {
         let bits = BitStream::from_u64(&[0x1234cdef]);
@@ -377,11 +676,11 @@ mod tests {
     #[test]
     fn test_bitstream_try_from() {
         let slice: [usize; 1] = [0xFFEEDDCCBBAA9988];
-        let bits = BitStream::try_from_val(&slice);
+        let bits = BitStream::try_from_val(&slice).unwrap();
         let mut result: usize = 0;
-        assert_eq!(bits.len(), size_of::<usize>() * 8);
-        for idx in 0..size_of::<usize>() * 8 {
-            result |= (bits.get(idx) as usize) << (size_of::<usize>() * 8 - 1 - idx);
+        assert_eq!(bits.len(), usize::BITS as usize);
+        for idx in 0..usize::BITS as usize {
+            result |= (bits.get(idx) as usize) << (usize::BITS as usize - 1 - idx);
         }
         assert_eq!(result, 0xFFEEDDCCBBAA9988);
     }
@@ -391,7 +690,7 @@ mod tests {
         let slice: [u8; 11] = [
             0xFF, 0xEE, 0xDD, 0xCC, 0xBB, 0xAA, 0x99, 0x88, 0x77, 0x66, 0x55,
         ];
-        let bits = BitStream::try_from_val(&slice);
+        let bits = BitStream::try_from_val(&slice).unwrap();
         let mut result1: u64 = 0;
         let mut result2: u64 = 0;
         assert_eq!(bits.len(), slice.len() * 8);
@@ -405,13 +704,513 @@ mod tests {
         assert_eq!(result2, 0x7766550000000000);
     }
 
+    #[test]
+    fn test_bitstream_errors() {
+        let mut bits = BitStream::new(12);
+        assert_eq!(bits.try_set(11, 1), Ok(()));
+        assert_eq!(bits.try_get(11), Ok(1));
+        assert_eq!(
+            bits.try_get(12),
+            Err(Error::OutOfBounds { index: 12, len: 12 })
+        );
+        assert_eq!(
+            bits.try_set(12, 1),
+            Err(Error::OutOfBounds { index: 12, len: 12 })
+        );
+        assert_eq!(
+            BitStream::try_from_val(&[-1_i64]).unwrap_err(),
+            Error::Conversion
+        );
+        let mut bytes = BitStream::from_val(&[0xAB_u8]);
+        assert_eq!(bytes.try_add_val(&[1_i32, -1]), Err(Error::Conversion));
+        assert_eq!(bytes.len(), 8);
+    }
+
+    #[test]
+    fn test_bitstream_append() {
+        let mut bits = BitStream::new(0);
+        bits.push(1);
+        bits.extend_bits([0, 1, 1]);
+        assert_eq!(bits.len(), 4);
+        bits.add_val(&[0xFF_u8, 0x00]);
+        assert_eq!(bits.len(), 20);
+        assert_eq!(bits.to_bytes(), [0xBF, 0xF0, 0x00]);
+        bits.try_add_val(&[0x1234_5678_9ABC_DEF0_u64]).unwrap();
+        assert_eq!(bits.len(), 84);
+        assert_eq!(
+            bits.as_vec_u64(),
+            [0xBFF0_0123_4567_89AB, 0xCDEF000000000000]
+        );
+
+        let head = BitStream::from_partial_bytes(&[0xA0], 3).unwrap();
+        let tail = BitStream::from_u64(&[u64::MAX]);
+        let joined = head.concat(&tail);
+        assert_eq!(joined.len(), 67);
+        assert_eq!(joined.to_bytes()[..2], [0xBF, 0xFF]);
+        assert_eq!(joined.to_bytes()[8], 0xE0);
+        assert_eq!(&head + &tail, joined);
+        let mut sum = BitStream::new(0);
+        sum += &head;
+        assert_eq!(sum + &tail, joined);
+    }
+
+    #[test]
+    fn test_bitstream_ops() {
+        let a = BitStream::from_partial_bytes(
+            &[0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xF0, 0xC0],
+            66,
+        )
+        .unwrap();
+        let b = BitStream::from_partial_bytes(
+            &[0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0x40],
+            66,
+        )
+        .unwrap();
+        assert_eq!(
+            (&a ^ &b).to_bytes(),
+            [0x0F, 0xF0, 0x0F, 0xF0, 0x0F, 0xF0, 0x0F, 0xF0, 0x80]
+        );
+        assert_eq!(
+            (&a & &b).to_bytes(),
+            [0xF0, 0x00, 0xF0, 0x00, 0xF0, 0x00, 0xF0, 0x00, 0x40]
+        );
+        assert_eq!(
+            (&a | &b).to_bytes(),
+            [0xFF, 0xF0, 0xFF, 0xF0, 0xFF, 0xF0, 0xFF, 0xF0, 0xC0]
+        );
+        assert_eq!(
+            (!&b).to_bytes(),
+            [0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x80]
+        );
+        assert_eq!((!&b).count_ones(), 33);
+        assert_eq!(
+            !!BitStream::from_bytes(&[0x5A]),
+            BitStream::from_bytes(&[0x5A])
+        );
+        let mut c = BitStream::from_bytes(&a.to_bytes());
+        c.truncate(66);
+        c ^= &b;
+        assert_eq!(c, &a ^ &b);
+        c ^= &b;
+        assert_eq!(c, a);
+        assert_eq!(a.count_ones(), 34);
+        assert_eq!(a.hamming_distance(&b), (&a ^ &b).count_ones());
+        assert_eq!(a.hamming_distance(&a), 0);
+        assert_eq!(a.try_xor(&b), Ok(&a ^ &b));
+        assert_eq!(a.try_and(&b), Ok(&a & &b));
+        assert_eq!(a.try_or(&b), Ok(&a | &b));
+        let mismatch = Err(Error::Mismatch {
+            left: 66,
+            right: 65,
+        });
+        let mut d = b.clone();
+        d.truncate(65);
+        assert_eq!(a.try_xor(&d), mismatch);
+        assert_eq!(a.try_and(&d), mismatch);
+        assert_eq!(a.try_or(&d), mismatch);
+        assert_eq!(
+            a.try_hamming_distance(&d),
+            Err(Error::Mismatch {
+                left: 66,
+                right: 65
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bitstream_op_mismatch() {
+        let _ = &BitStream::new(3) ^ &BitStream::new(4);
+    }
+
+    #[test]
+    fn test_bitstream_slice() {
+        let bits = BitStream::from_u64(&[0x0123_4567_89AB_CDEF, 0xFEDC_BA98_7654_3210]);
+        let view = bits.slice(4..72);
+        assert_eq!(view.len(), 68);
+        assert_eq!(view.get(3), 1);
+        assert_eq!(
+            view.to_bit_stream().as_vec_u64(),
+            [0x1234_5678_9ABC_DEFF, 0xE000_0000_0000_0000]
+        );
+        assert_eq!(view.slice(60..), bits.slice(64..72));
+        assert_eq!(view.count_ones(), 39);
+        let (head, tail) = bits.split_at(100);
+        assert_eq!(head.len() + tail.len(), 128);
+        assert_eq!(tail.to_bit_stream().as_vec_u64(), [0x7654_3210 << 36]);
+        assert_eq!(
+            bits.try_slice(120..130).unwrap_err(),
+            Error::OutOfBounds {
+                index: 130,
+                len: 128
+            }
+        );
+        let (start, end) = (5, 4);
+        assert!(bits.try_slice(start..end).is_err());
+        let max_bound = Error::OutOfBounds {
+            index: usize::MAX,
+            len: 128,
+        };
+        assert_eq!(bits.try_slice(..=usize::MAX).unwrap_err(), max_bound);
+        assert_eq!(
+            bits.try_slice((
+                core::ops::Bound::Excluded(usize::MAX),
+                core::ops::Bound::Unbounded
+            ))
+            .unwrap_err(),
+            max_bound
+        );
+        assert!(bits.slice(128..).is_empty());
+
+        let mut short = BitStream::from_u64(&[u64::MAX]);
+        short.truncate(3);
+        assert_eq!(short.len(), 3);
+        assert_eq!(short.as_vec_u64(), [0xE000_0000_0000_0000]);
+    }
+
+    #[test]
+    fn test_bitstream_rotate() {
+        let mut bits = BitStream::from_partial_bytes(&[0b1100_0000, 0b0100_0000], 10).unwrap();
+        bits.rotate_left(1);
+        assert_eq!(bits.to_bytes(), [0b1000_0000, 0b1100_0000]);
+        bits.rotate_right(1);
+        assert_eq!(bits.to_bytes(), [0b1100_0000, 0b0100_0000]);
+        bits.rotate_left(21);
+        assert_eq!(bits.to_bytes(), [0b1000_0000, 0b1100_0000]);
+        let mut long = BitStream::from_u64(&[0x8000_0000_0000_0001, 0x1]);
+        long.rotate_right(64);
+        assert_eq!(long.as_vec_u64(), [0x1, 0x8000_0000_0000_0001]);
+    }
+
+    #[test]
+    fn test_bitstream_iter() {
+        let bits = BitStream::from_u64(&[0x8000_0000_0000_0001, 0xC000_0000_0000_0000]);
+        let set: Vec<usize> = bits
+            .iter()
+            .enumerate()
+            .filter(|&(_, bit)| bit == 1)
+            .map(|(idx, _)| idx)
+            .collect();
+        assert_eq!(set, [0, 63, 64, 65]);
+        assert_eq!(bits.iter().len(), 128);
+        assert_eq!(bits.slice(62..66).iter().collect::<Vec<_>>(), [0, 1, 1, 1]);
+        assert_eq!(bits.words().collect::<Vec<_>>(), bits.as_vec_u64());
+        assert_eq!(bits.bytes().collect::<Vec<_>>(), bits.to_bytes());
+        assert_eq!(bits.slice(60..70).bytes().collect::<Vec<_>>(), [0x1C, 0x00]);
+
+        let chunks: Vec<BitStream> = bits.chunks(50).map(|c| c.to_bit_stream()).collect();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[2].len(), 28);
+        assert_eq!(
+            chunks.iter().fold(BitStream::new(0), |acc, c| acc + c),
+            bits
+        );
+
+        let collected: BitStream = bits.iter().collect();
+        assert_eq!(collected, bits);
+        let mut flags: BitStream = [true, false, true].iter().copied().collect();
+        flags.extend([true]);
+        flags.extend([0_u8, 1]);
+        assert_eq!(flags.to_bytes(), [0b1011_0100]);
+        assert_eq!(flags.len(), 6);
+    }
+
+    #[test]
+    fn test_bitstream_text() {
+        let bits = BitStream::from_partial_bytes(&[0xA5, 0x3C], 14).unwrap();
+        assert_eq!(bits.to_string(), "10100101001111");
+        assert_eq!(format!("{:#b}", bits), "0b10100101001111");
+        assert_eq!(format!("{:x}", bits), "a53c");
+        assert_eq!(format!("{:#X}", bits), "0xA53C");
+        let mut odd = BitStream::from_bytes(&[0xA5, 0x3C]);
+        odd.truncate(12);
+        assert_eq!(format!("{:x}", odd), "a53");
+        assert_eq!(BitStream::from_hex("a53").unwrap(), odd);
+        assert_eq!("10100101001111".parse::<BitStream>().unwrap(), bits);
+        assert_eq!(
+            BitStream::from_bin_str("0b 1010 0101\n0011 11").unwrap(),
+            bits
+        );
+        assert_eq!(
+            BitStream::from_hex("0xA5 3C").unwrap().to_bytes(),
+            [0xA5, 0x3C]
+        );
+        assert_eq!(BitStream::from_hex("a5g"), Err(Error::Parse { index: 2 }));
+        assert_eq!(
+            BitStream::from_bin_str("0102"),
+            Err(Error::Parse { index: 3 })
+        );
+        assert!(BitStream::from_hex("").unwrap().is_empty());
+        assert_eq!(BitStream::new(0).to_string(), "");
+
+        // RFC 4648 test vectors
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (plain, encoded) in vectors {
+            let bits = BitStream::from_bytes(plain.as_bytes());
+            assert_eq!(bits.to_base64(), encoded);
+            assert_eq!(BitStream::from_base64(encoded).unwrap(), bits);
+            assert_eq!(
+                BitStream::from_base64(encoded.trim_end_matches('=')).unwrap(),
+                bits
+            );
+        }
+        assert_eq!(
+            BitStream::from_base64("Zm9v\nYmFy").unwrap().to_bytes(),
+            b"foobar"
+        );
+        assert!(BitStream::from_base64("Zm9vY").is_err());
+        assert!(BitStream::from_base64("Zg=a").is_err());
+        assert!(BitStream::from_base64("Zg===").is_err());
+        assert!(BitStream::from_base64("Z!==").is_err());
+    }
+
+    #[test]
+    fn test_bitstream_read() {
+        let bits = BitStream::from_u64(&[0x0123_4567_89AB_CDEF, 0xFEDC_BA98_7654_3210]);
+        assert_eq!(bits.read_u8(0, Endian::Big), Ok(0x01));
+        assert_eq!(bits.read_u8(4, Endian::Little), Ok(0x12));
+        assert_eq!(bits.read_u16(60, Endian::Big), Ok(0xFFED));
+        assert_eq!(bits.read_u16(60, Endian::Little), Ok(0xEDFF));
+        assert_eq!(bits.read_u32(4, Endian::Big), Ok(0x1234_5678));
+        assert_eq!(bits.read_u64(0, Endian::Big), Ok(0x0123_4567_89AB_CDEF));
+        assert_eq!(bits.read_u64(0, Endian::Little), Ok(0xEFCD_AB89_6745_2301));
+        assert_eq!(bits.read_u64(8, Endian::Big), Ok(0x2345_6789_ABCD_EFFE));
+        assert_eq!(
+            bits.read_u128(0, Endian::Big),
+            Ok(0x0123_4567_89AB_CDEF_FEDC_BA98_7654_3210)
+        );
+        assert_eq!(
+            bits.read_u128(0, Endian::Little),
+            Ok(0x1032_5476_98BA_DCFE_EFCD_AB89_6745_2301)
+        );
+        assert_eq!(
+            bits.read_u64(65, Endian::Big),
+            Err(Error::OutOfBounds {
+                index: 129,
+                len: 128
+            })
+        );
+        assert!(bits.read_u8(usize::MAX, Endian::Big).is_err());
+        assert_eq!(bits.read_u8(120, Endian::Big), Ok(0x10));
+    }
+
+    #[test]
+    fn test_keccak_lane() {
+        let lanes: Vec<u64> = (0..25).map(|idx| 0x0101_0101_0101_0101 * idx + 1).collect();
+        let k = Keccak::new(&BitStream::from_u64_with_order(&lanes, BitOrder::LsbFirst));
+        for y in 0..5 {
+            for x in 0..5 {
+                assert_eq!(k.lane(x, y), lanes[5 * y + x]);
+            }
+        }
+        let narrow: Vec<u8> = (0..25).map(|idx| 7 * idx + 3).collect();
+        let k = Keccak::new(&BitStream::from_val_with_order(&narrow, BitOrder::LsbFirst));
+        assert_eq!(k.depth(), 8);
+        assert_eq!(k.lane(3, 2), narrow[13] as u64);
+        let first = k.get_lane(0, 0);
+        assert_eq!(first.len(), 8);
+        for (z, bit) in first.iter().enumerate() {
+            assert_eq!(*bit as u64, (k.lane(0, 0) >> z) & 1);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_keccak_get_lane_out_of_range() {
+        // z only goes up to the depth, so (5, 4) would otherwise read past
+        // the end of the state
+        Keccak::zeroed(200).get_lane(5, 4);
+    }
+
+    #[test]
+    fn test_keccak_from_lanes_and_bytes() {
+        let lanes: [u64; 25] =
+            core::array::from_fn(|idx| 0x9E37_79B9_7F4A_7C15_u64.wrapping_mul(idx as u64 + 1));
+        let k = Keccak::from_lanes(&lanes);
+        assert_eq!(k.depth(), 64);
+        let mut bytes = [0_u8; 200];
+        let reference = KeccakF1600::from_lanes(lanes);
+        reference.extract_bytes(0, &mut bytes);
+        let from_bytes = Keccak::from_bytes(&bytes, 1600).unwrap();
+        assert_eq!(from_bytes.get_state(), k.get_state());
+        for (idx, &lane) in lanes.iter().enumerate() {
+            assert_eq!(k.lane(idx % 5, idx / 5), lane);
+        }
+
+        // the narrower widths map bytes the same way KeccakF does
+        let mut narrow = KeccakF800::new();
+        narrow.xor_bytes(3, &bytes[..90]);
+        let mut k = Keccak::zeroed(800);
+        k.xor_bytes_into_state(3, &bytes[..90]).unwrap();
+        for idx in 0..25 {
+            assert_eq!(k.lane(idx % 5, idx / 5), narrow.lanes()[idx] as u64);
+        }
+        let tiny = Keccak::from_bytes(&[0xFF; 4], 25).unwrap();
+        assert_eq!(tiny.get_state().count_ones(), 25);
+
+        let mut k = Keccak::zeroed(100);
+        assert_eq!(k.depth(), 4);
+        k.set_lane(2, 3, 0xFA);
+        assert_eq!(k.lane(2, 3), 0xA);
+        k.xor_lane(2, 3, 0x3);
+        assert_eq!(k.lane(2, 3), 0x9);
+        assert_eq!(
+            k.xor_bytes_into_state(12, &[1, 2]),
+            Err(Error::OutOfBounds { index: 14, len: 13 })
+        );
+        assert_eq!(k.lane(2, 3), 0x9);
+        assert_eq!(
+            Keccak::from_bytes(&[0; 26], 200)
+                .err()
+                .map(|e| e.to_string()),
+            Some(Error::OutOfBounds { index: 26, len: 25 }.to_string())
+        );
+        assert!(Keccak::from_bytes(&[], 300).is_err());
+        assert!(Keccak::from_bytes(&[], 3200).is_err());
+    }
+
+    #[test]
+    fn test_keccak_try_new() {
+        let bits = BitStream::new(100);
+        assert_eq!(Keccak::try_new(&bits).unwrap().depth(), 4);
+        assert_eq!(Keccak::try_new_sized(&bits, 64).unwrap().depth(), 64);
+        assert_eq!(
+            Keccak::try_new(&BitStream::new(75)).err(),
+            Some(Error::InvalidWidth { width: 75 })
+        );
+        for size in [0, 3, 128, usize::MAX / 25 + 1] {
+            assert!(matches!(
+                Keccak::try_new_sized(&bits, size),
+                Err(Error::InvalidWidth { .. })
+            ));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_keccak_new_sized_invalid() {
+        Keccak::new_sized(&BitStream::new(0), 37);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_keccak_zeroed_invalid() {
+        Keccak::zeroed(1000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bitstream_panic_set() {
+        BitStream::new(12).set(12, 1);
+    }
+
+    #[test]
+    fn test_bitstream_bytes() {
+        let bits = BitStream::from_bytes(&[0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0, 0x0F]);
+        assert_eq!(bits.bit_len(), 72);
+        assert_eq!(bits.byte_len(), 9);
+        assert_eq!(bits.as_vec_u64(), [0x123456789ABCDEF0, 0x0F00000000000000]);
+        assert_eq!(bits.to_bytes()[7..], [0xF0, 0x0F]);
+        assert_eq!(BitStream::from(bits.to_bytes()), bits);
+        assert_eq!(
+            BitStream::from(&[0xAB_u8][..]),
+            BitStream::from_val(&[0xAB_u8])
+        );
+
+        let partial = BitStream::from_partial_bytes(&[0xFF, 0xFF], 11).unwrap();
+        assert_eq!(partial.bit_len(), 11);
+        assert_eq!(partial.byte_len(), 2);
+        assert_eq!(Vec::from(&partial), [0xFF, 0xE0]);
+        assert_eq!(
+            BitStream::from_partial_bytes(&[0xFF], 11).unwrap_err(),
+            Error::Length {
+                expected: 2,
+                actual: 1
+            }
+        );
+        assert!(BitStream::from_bytes(&[]).to_bytes().is_empty());
+        // new over-allocates words, which shouldn't show up in the export
+        assert_eq!(BitStream::new(65).as_vec_u64().len(), 2);
+    }
+
+    #[test]
+    fn test_bit_order() {
+        let msb = BitStream::from_bytes_with_order(&[0x01, 0x80], BitOrder::MsbFirst);
+        let lsb = BitStream::from_bytes_with_order(&[0x01, 0x80], BitOrder::LsbFirst);
+        assert_eq!(msb.get(7), 1);
+        assert_eq!(msb.get(8), 1);
+        assert_eq!(lsb.get(0), 1);
+        assert_eq!(lsb.get(15), 1);
+        assert_eq!(msb, BitStream::from_bytes(&[0x01, 0x80]));
+        assert_eq!(
+            msb.convert_order(BitOrder::MsbFirst, BitOrder::LsbFirst)
+                .unwrap(),
+            lsb
+        );
+        assert_eq!(lsb.to_bytes_with_order(BitOrder::LsbFirst), [0x01, 0x80]);
+        assert_eq!(lsb.to_bytes_with_order(BitOrder::MsbFirst), [0x80, 0x01]);
+
+        // bit i of a value is bit i of the stream, least significant byte first
+        let val = BitStream::from_val_with_order(&[0x0102_u16], BitOrder::LsbFirst);
+        let set: Vec<usize> = (0..16).filter(|&idx| val.get(idx) == 1).collect();
+        assert_eq!(set, [1, 8]);
+        let val = BitStream::from_val_with_order(&[0x0102_u16], BitOrder::MsbFirst);
+        let set: Vec<usize> = (0..16).filter(|&idx| val.get(idx) == 1).collect();
+        assert_eq!(set, [7, 14]);
+        assert_eq!(
+            BitStream::from_bin_str_with_order("01100001", BitOrder::LsbFirst)
+                .unwrap()
+                .to_bytes(),
+            [0x86]
+        );
+        assert_eq!(
+            BitStream::from_bin_str_with_order("0b 01100001 011", BitOrder::LsbFirst).unwrap(),
+            BitStream::from_bin_str("10000110 110").unwrap()
+        );
+
+        let partial = BitStream::from_partial_bytes(&[0xF0], 3).unwrap();
+        assert_eq!(partial.to_bytes_with_order(BitOrder::LsbFirst), [0x07]);
+        assert_eq!(
+            partial.convert_order(BitOrder::MsbFirst, BitOrder::LsbFirst),
+            Err(Error::Unaligned { len: 3 })
+        );
+    }
+
+    #[test]
+    fn test_bit_order_fips() {
+        // FIPS 202 puts bit z of lane (x, y) at 64 * (5y + x) + z
+        let mut k = KeccakF1600::new();
+        k.permute();
+        let bits = BitStream::from_u64_with_order(k.lanes(), BitOrder::LsbFirst);
+        for (idx, lane) in k.lanes().iter().enumerate() {
+            for z in 0..64 {
+                assert_eq!(bits.get(64 * idx + z) as u64, (lane >> z) & 1);
+            }
+        }
+        let mut bytes = [0_u8; 200];
+        k.extract_bytes(0, &mut bytes);
+        assert_eq!(bits.to_bytes_with_order(BitOrder::LsbFirst), bytes);
+        assert_eq!(
+            BitStream::from_bytes_with_order(&bytes, BitOrder::LsbFirst),
+            bits
+        );
+    }
+
     #[test]
     fn test_bitstream_from_str() {
-        let bits = BitStream::from_str("abcdefgh");
+        let bits = BitStream::from_bytes(b"abcdefgh");
         assert_eq!(bits.len(), 64);
         let mut result: i64 = 0;
         for idx in 0..bits.len() {
-            result |= (bits.get(idx) as i64) << 63 - idx;
+            result |= (bits.get(idx) as i64) << (63 - idx);
         }
         assert_eq!(result, 0x6162636465666768);
     }
@@ -419,14 +1218,14 @@ mod tests {
     #[test]
     fn test_keccak_new() {
         // create 200 bit, 5*5*8 state array
-        let state = Keccak::new(&BitStream::from_str("abcdefghijklmnopqrstuvwxy"));
+        let state = Keccak::new(&BitStream::from_bytes(b"abcdefghijklmnopqrstuvwxy"));
         assert_eq!(state.get_lane(0, 0), vec!(0, 1, 1, 0, 0, 0, 0, 1));
         assert_eq!(state.get_lane(4, 4), vec!(0, 1, 1, 1, 1, 0, 0, 1))
     }
 
     #[test]
     fn test_keccak() {
-        let bits = BitStream::from_str("twenty-five-characters ! ");
+        let bits = BitStream::from_bytes(b"twenty-five-characters ! ");
         let mut k1 = Keccak::new(&bits);
         let mut k2 = Keccak::new(&bits);
         assert_eq!(k1.depth(), 8);
@@ -438,14 +1237,819 @@ mod tests {
 
     #[test]
     fn test_theta() {
-        let mut k = Keccak::new(&BitStream::from_str(
-            "1\0\0\0\01\0\0\0\01\0\0\0\01\0\0\0\01\0\0\0\0",
+        let mut k = Keccak::new(&BitStream::from_bytes(
+            b"1\x00\x00\x00\x001\x00\x00\x00\x001\x00\x00\x00\x001\x00\x00\x00\x001\x00\x00\x00\x00",
         ));
         let k1 = Keccak::new(&BitStream::from_val(&[
-            0x31, 0x31, 0x00, 0x00, 0x18, 0x31, 0x31, 0x00, 0x00, 0x18, 0x31, 0x31, 0x00, 0x00,
-            0x18, 0x31, 0x31, 0x00, 0x00, 0x18, 0x31, 0x31, 0x00, 0x00, 0x18_u8,
+            0x31, 0x31, 0x00, 0x00, 0x98, 0x31, 0x31, 0x00, 0x00, 0x98, 0x31, 0x31, 0x00, 0x00,
+            0x98, 0x31, 0x31, 0x00, 0x00, 0x98, 0x31, 0x31, 0x00, 0x00, 0x98_u8,
         ]));
         k.theta();
         assert_eq!(k.get_state(), k1.get_state());
     }
+
+    /// one permutation from a KeccakF-{width}-IntermediateValues.txt, the
+    /// states after each step mapping are labelled "After theta" and so on
+    struct Trace {
+        input: Vec<u8>,
+        steps: Vec<(String, Vec<u8>)>,
+        output: Vec<u8>,
+    }
+
+    /// the traces in tests/vectors/dir, see the README there. Each state is
+    /// the lines of hex bytes after its label, other labelled blocks, like
+    /// the same state written as lanes, are skipped.
+    fn intermediate_values(dir: &str, width: usize) -> Vec<Trace> {
+        let path = format!(
+            "{}/tests/vectors/{}/KeccakF-{}-IntermediateValues.txt",
+            env!("CARGO_MANIFEST_DIR"),
+            dir,
+            width
+        );
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) => panic!("{}: {}", path, err),
+        };
+        let mut traces = Vec::new();
+        let mut lines = text.lines().map(str::trim).peekable();
+        while let Some(line) = lines.next() {
+            // a label ends with a colon, the state follows up to a blank
+            // line or the next label
+            let label = match line.strip_suffix(':') {
+                Some(label) => label,
+                None => continue,
+            };
+            let mut rows = Vec::new();
+            while let Some(row) = lines.next_if(|row| !row.is_empty() && !row.ends_with(':')) {
+                rows.push(row);
+            }
+            let state = || match BitStream::from_hex(&rows.join(" ")) {
+                Ok(bits) => bits.to_bytes(),
+                Err(err) => panic!("{}: {}: {}", path, label, err),
+            };
+            match label {
+                "Input of permutation" => traces.push(Trace {
+                    input: state(),
+                    steps: Vec::new(),
+                    output: Vec::new(),
+                }),
+                "State after permutation" => traces.last_mut().unwrap().output = state(),
+                "After theta" | "After rho" | "After pi" | "After chi" | "After iota" => traces
+                    .last_mut()
+                    .unwrap()
+                    .steps
+                    .push((label.into(), state())),
+                _ => continue,
+            }
+        }
+        assert_eq!(traces.len(), 2, "{}", path);
+        return traces;
+    }
+
+    /// applies a step mapping, or a whole round, to the state in FIPS 202
+    /// bytes
+    fn bit_level_step(width: usize, state: &[u8], step: &str, round: usize) -> Vec<u8> {
+        let mut k = Keccak::from_bytes(state, width).unwrap();
+        match step {
+            "After theta" => k.theta(),
+            "After rho" => k.rho(),
+            "After pi" => k.pi(),
+            "After chi" => k.chi(),
+            "After iota" => k.iota(round as u64),
+            "round" => k.round(round as u64),
+            _ => panic!("unknown step {}", step),
+        }
+        return k.get_state().to_bytes_with_order(BitOrder::LsbFirst);
+    }
+
+    fn lane_step<L: Lane>(state: &[u8], step: &str, round: usize) -> Vec<u8> {
+        let mut k = KeccakF::<L>::new();
+        k.xor_bytes(0, state);
+        match step {
+            "After theta" => k.theta(),
+            "After rho" => k.rho(),
+            "After pi" => k.pi(),
+            "After chi" => k.chi(),
+            "After iota" => k.iota(round),
+            "round" => k.round(round),
+            _ => panic!("unknown step {}", step),
+        }
+        let mut temp = vec![0; KeccakF::<L>::BYTES];
+        k.extract_bytes(0, &mut temp);
+        return temp;
+    }
+
+    /// the step from every implementation of the width, with its name
+    fn all_steps(
+        width: usize,
+        state: &[u8],
+        step: &str,
+        round: usize,
+    ) -> Vec<(&'static str, Vec<u8>)> {
+        let mut temp = vec![("Keccak", bit_level_step(width, state, step, round))];
+        match width {
+            200 => temp.push(("KeccakF200", lane_step::<u8>(state, step, round))),
+            400 => temp.push(("KeccakF400", lane_step::<u16>(state, step, round))),
+            800 => temp.push(("KeccakF800", lane_step::<u32>(state, step, round))),
+            1600 => temp.push(("KeccakF1600", lane_step::<u64>(state, step, round))),
+            _ => {}
+        }
+        return temp;
+    }
+
+    const WIDTHS: [usize; 7] = [25, 50, 100, 200, 400, 800, 1600];
+
+    /// each step mapping starts from the expected state before it, so a
+    /// failure points at the one step and round that is wrong
+    fn check_step_mappings(dir: &str) {
+        for width in WIDTHS {
+            for trace in intermediate_values(dir, width) {
+                let mut before = &trace.input;
+                for (idx, (step, expected)) in trace.steps.iter().enumerate() {
+                    let round = idx / 5;
+                    for (name, actual) in all_steps(width, before, step, round) {
+                        assert_eq!(
+                            hex(&actual),
+                            hex(expected),
+                            "{}, width {}, round {}, {}",
+                            name,
+                            width,
+                            round,
+                            step
+                        );
+                    }
+                    before = expected;
+                }
+            }
+        }
+    }
+
+    fn check_rounds(dir: &str) {
+        for width in WIDTHS {
+            for trace in intermediate_values(dir, width) {
+                let mut before = &trace.input;
+                // the state after iota ends each round
+                for (round, steps) in trace.steps.chunks(5).enumerate() {
+                    let expected = &steps[4].1;
+                    for (name, actual) in all_steps(width, before, "round", round) {
+                        assert_eq!(
+                            hex(&actual),
+                            hex(expected),
+                            "{}, width {}, round {}",
+                            name,
+                            width,
+                            round
+                        );
+                    }
+                    before = expected;
+                }
+                let mut k = Keccak::from_bytes(&trace.input, width).unwrap();
+                k.keccak(trace.steps.len() as u64 / 5);
+                let actual = k.get_state().to_bytes_with_order(BitOrder::LsbFirst);
+                assert_eq!(hex(&actual), hex(&trace.output), "width {}", width);
+            }
+        }
+    }
+
+    #[test]
+    fn test_step_mappings() {
+        check_step_mappings("generated");
+    }
+
+    #[test]
+    fn test_rounds() {
+        check_rounds("generated");
+    }
+
+    #[test]
+    #[ignore = "needs the Keccak team's files in tests/vectors/xkcp, see the README there"]
+    fn test_xkcp_step_mappings() {
+        check_step_mappings("xkcp");
+    }
+
+    #[test]
+    #[ignore = "needs the Keccak team's files in tests/vectors/xkcp, see the README there"]
+    fn test_xkcp_rounds() {
+        check_rounds("xkcp");
+    }
+
+    #[test]
+    fn test_ctr_matches_sequential() {
+        let seed = BitStream::from_u64(&[0xdeadbeef]);
+        let mut seq = Keccak::new_sized(&seed, 8);
+        seq.keccak(18);
+        let mut ctr = KeccakCtr::new(&seed, 8, 18);
+        assert_eq!(ctr.next_u64(), seq.copy_to_u64());
+        assert_eq!(ctr.position(), 1);
+    }
+
+    #[test]
+    fn test_ctr_seek() {
+        let seed = BitStream::from_bytes(b"seek");
+        for depth in [8, 16, 64] {
+            let mut seq = KeccakCtr::new(&seed, depth, 12);
+            let mut vals = [0_u64; 40];
+            seq.fill(&mut vals);
+            let mut ctr = KeccakCtr::new(&seed, depth, 12);
+            for pos in [39, 0, 17, 3, 18, 4] {
+                ctr.seek(pos);
+                assert_eq!(ctr.position(), pos);
+                assert_eq!(ctr.next_u64(), vals[pos as usize]);
+                assert_eq!(ctr.position(), pos + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ctr_far_seek() {
+        let seed = BitStream::from_u64(&[7]);
+        let mut ctr = KeccakCtr::new(&seed, 8, 18);
+        ctr.seek(10_000_000);
+        let val = ctr.next_u64();
+        ctr.seek(10_000_000);
+        assert_eq!(ctr.next_u64(), val);
+        ctr.seek(0);
+        assert_ne!(ctr.next_u64(), val);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ctr_too_many_rounds() {
+        KeccakCtr::new(&BitStream::from_u64(&[7]), 8, 19);
+    }
+
+    #[test]
+    fn test_ctr_zero_rounds() {
+        assert_eq!(
+            KeccakCtr::try_new(&BitStream::from_u64(&[7]), 64, 0).err(),
+            Some(Error::TooFewRounds { rounds: 0, min: 1 })
+        );
+        let mut saved = KeccakCtr::new(&BitStream::from_u64(&[7]), 64, 24).save();
+        saved[6] = 0;
+        assert_eq!(KeccakCtr::restore(&saved).err(), Some(Error::InvalidFormat));
+    }
+
+    #[test]
+    fn test_ctr_save_restore() {
+        let mut ctr = KeccakCtr::new(&BitStream::from_bytes(b"checkpoint"), 16, 12);
+        let mut before = [0_u64; 5];
+        ctr.fill(&mut before);
+        ctr.reseed(&BitStream::from_u64(&[42]));
+        ctr.next_u64();
+        let saved = ctr.save();
+        let mut resumed = KeccakCtr::restore(&saved).unwrap();
+        assert_eq!(resumed.position(), 6);
+        assert_eq!(resumed.reseed_count(), 1);
+        assert_eq!(resumed.save(), saved);
+        let mut expected = [0_u64; 10];
+        let mut actual = [0_u64; 10];
+        ctr.fill(&mut expected);
+        resumed.fill(&mut actual);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ctr_restore_invalid() {
+        let saved = KeccakCtr::new(&BitStream::from_u64(&[1]), 8, 18).save();
+        assert!(KeccakCtr::restore(&saved).is_ok());
+        assert_eq!(
+            KeccakCtr::restore(&saved[..saved.len() - 1]).err(),
+            Some(Error::InvalidFormat)
+        );
+        let mut bad_version = saved.clone();
+        bad_version[4] = 2;
+        assert!(KeccakCtr::restore(&bad_version).is_err());
+        let mut bad_rounds = saved.clone();
+        bad_rounds[6] = 19;
+        assert!(KeccakCtr::restore(&bad_rounds).is_err());
+    }
+
+    #[test]
+    fn test_keccak_snapshot() {
+        let mut k = Keccak::new(&BitStream::from_bytes(b"twenty-five-characters ! "));
+        k.keccak(18);
+        let snapshot = k.snapshot();
+        assert_eq!(snapshot.depth, 8);
+        let mut restored = Keccak::from_snapshot(&snapshot);
+        restored.keccak(18);
+        k.keccak(18);
+        assert_eq!(restored.get_state(), k.get_state());
+    }
+
+    #[test]
+    fn test_keccak_save_restore() {
+        for depth in [1, 2, 8, 64] {
+            let mut k = Keccak::new_sized(&BitStream::from_bytes(b"checkpoint"), depth);
+            k.keccak(5);
+            let saved = k.save();
+            assert_eq!(saved.len(), 6 + (25 * depth).div_ceil(8));
+            let mut resumed = Keccak::restore(&saved).unwrap();
+            assert_eq!(resumed.depth(), depth);
+            assert_eq!(resumed.save(), saved);
+            k.keccak(5);
+            resumed.keccak(5);
+            assert_eq!(resumed.get_state(), k.get_state());
+        }
+    }
+
+    #[test]
+    fn test_keccak_restore_invalid() {
+        let saved = Keccak::new_sized(&BitStream::from_u64(&[1]), 1).save();
+        assert!(Keccak::restore(&saved).is_ok());
+        assert_eq!(
+            Keccak::restore(&saved[..saved.len() - 1]).err(),
+            Some(Error::InvalidFormat)
+        );
+        let mut bad_version = saved.clone();
+        bad_version[4] = 2;
+        assert_eq!(
+            Keccak::restore(&bad_version).err(),
+            Some(Error::InvalidFormat)
+        );
+        for depth in [0, 3, 128, 255] {
+            let mut bad_depth = saved.clone();
+            bad_depth[5] = depth;
+            assert_eq!(
+                Keccak::restore(&bad_depth).err(),
+                Some(Error::InvalidWidth {
+                    width: 25 * depth as usize
+                })
+            );
+        }
+        let mut bad_padding = saved.clone();
+        *bad_padding.last_mut().unwrap() |= 1;
+        assert_eq!(
+            Keccak::restore(&bad_padding).err(),
+            Some(Error::InvalidFormat)
+        );
+        let ctr = KeccakCtr::new(&BitStream::from_u64(&[1]), 8, 18).save();
+        assert_eq!(Keccak::restore(&ctr).err(), Some(Error::InvalidFormat));
+    }
+
+    #[test]
+    fn test_keccak_snapshot_invalid() {
+        let state = BitStream::new(200);
+        for depth in [0, 3, 128, usize::MAX / 25 + 1] {
+            let snapshot = crate::KeccakSnapshot {
+                depth,
+                state: state.clone(),
+            };
+            assert!(matches!(
+                Keccak::try_from_snapshot(&snapshot),
+                Err(Error::InvalidWidth { .. })
+            ));
+        }
+        let short = crate::KeccakSnapshot {
+            depth: 8,
+            state: BitStream::new(199),
+        };
+        assert_eq!(
+            Keccak::try_from_snapshot(&short).err(),
+            Some(Error::InvalidWidth { width: 199 })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_bitstream() {
+        let mut bits = BitStream::from_val(&[0x01_u8, 0xab]);
+        bits.set(15, 0);
+        let json = serde_json::to_string(&bits).unwrap();
+        assert_eq!(json, r#"{"length":16,"bits":"01aa"}"#);
+        let decoded = serde_json::from_str::<BitStream>(&json).unwrap();
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+        let encoded = bincode::serialize(&bits).unwrap();
+        let decoded = bincode::deserialize::<BitStream>(&encoded).unwrap();
+        assert_eq!(bincode::serialize(&decoded).unwrap(), encoded);
+        assert!(serde_json::from_str::<BitStream>(r#"{"length":17,"bits":"01aa"}"#).is_err());
+        assert!(serde_json::from_str::<BitStream>(r#"{"length":16,"bits":"01ag"}"#).is_err());
+        assert!(serde_json::from_str::<BitStream>(r#"{"length":16,"bits":"+1aa"}"#).is_err());
+        assert!(serde_json::from_str::<BitStream>(r#"{"length":16,"bits":"01-a"}"#).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_bitstream_partial_byte() {
+        let mut bits = BitStream::new(13);
+        bits.set(0, 1);
+        bits.set(12, 1);
+        let json = serde_json::to_string(&bits).unwrap();
+        assert_eq!(json, r#"{"length":13,"bits":"8008"}"#);
+        let decoded = serde_json::from_str::<BitStream>(&json).unwrap();
+        assert_eq!(decoded.len(), 13);
+        assert_eq!(decoded.get(12), 1);
+        let encoded = bincode::serialize(&bits).unwrap();
+        assert_eq!(
+            bincode::deserialize::<BitStream>(&encoded).unwrap().len(),
+            13
+        );
+        assert!(serde_json::from_str::<BitStream>(r#"{"length":13,"bits":"800c"}"#).is_err());
+        let mut padded = encoded.clone();
+        let last = padded.len() - 1;
+        padded[last] |= 1;
+        assert!(bincode::deserialize::<BitStream>(&padded).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_keccak_snapshot() {
+        let mut k = Keccak::new_sized(&BitStream::from_u64(&[0xdeadbeef]), 16);
+        k.keccak(20);
+        let json = serde_json::to_string(&k.snapshot()).unwrap();
+        let from_json: crate::KeccakSnapshot = serde_json::from_str(&json).unwrap();
+        let encoded = bincode::serialize(&k.snapshot()).unwrap();
+        let from_bincode: crate::KeccakSnapshot = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(from_json, k.snapshot());
+        assert_eq!(from_bincode, k.snapshot());
+        assert_eq!(Keccak::from_snapshot(&from_json).get_state(), k.get_state());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_keccak_snapshot_invalid() {
+        let cases = [
+            // 25 * depth wraps around to 9
+            r#"{"depth":737869762948382065,"state":{"length":9,"bits":"0000"}}"#,
+            r#"{"depth":0,"state":{"length":0,"bits":""}}"#,
+            r#"{"depth":3,"state":{"length":75,"bits":"00000000000000000000"}}"#,
+            r#"{"depth":1,"state":{"length":24,"bits":"000000"}}"#,
+        ];
+        for json in cases {
+            let err = serde_json::from_str::<crate::KeccakSnapshot>(json).unwrap_err();
+            assert!(
+                err.to_string().contains("unsupported permutation width"),
+                "{}",
+                err
+            );
+        }
+        let k = Keccak::new_sized(&BitStream::from_u64(&[1]), 2);
+        let encoded = bincode::serialize(&k.snapshot()).unwrap();
+        let mut bad_depth = encoded.clone();
+        bad_depth[0] = 3;
+        assert!(bincode::deserialize::<crate::KeccakSnapshot>(&bad_depth).is_err());
+    }
+
+    #[test]
+    fn test_ctr_gen() {
+        let mut ctr = KeccakCtr::new(&BitStream::from_u64(&[3]), 8, 18);
+        let val = ctr.next_u64();
+        ctr.seek(0);
+        assert_eq!(ctr.gen::<u8>(), (val >> 56) as u8);
+        ctr.seek(0);
+        assert_eq!(ctr.gen::<i32>(), (val >> 32) as i32);
+        ctr.seek(0);
+        assert_eq!(ctr.gen::<bool>(), val >> 63 == 1);
+        for _ in 0..20 {
+            let float = ctr.gen::<f64>();
+            assert!((0.0..1.0).contains(&float));
+        }
+    }
+
+    #[test]
+    fn test_thread_rng() {
+        let vals: Vec<u64> = (0..4).map(|_| random::<u64>()).collect();
+        assert_ne!(vals[0], vals[1]);
+        let mut rng = thread_rng();
+        let mut bytes = [0_u8; 13];
+        rng.fill_bytes(&mut bytes);
+        assert_ne!(bytes, [0_u8; 13]);
+        let reseeds = rng.reseed_count();
+        rng.reseed();
+        assert_eq!(thread_rng().reseed_count(), reseeds + 1);
+        let other = std::thread::spawn(random::<u128>).join().unwrap();
+        assert_ne!(other, random::<u128>());
+    }
+
+    #[test]
+    fn test_thread_rng_reseeds() {
+        let mut rng = ReseedingCtr::new(3);
+        for _ in 0..3 {
+            rng.with(|ctr| ctr.next_u64());
+        }
+        assert_eq!(rng.reseed_count(), 0);
+        rng.with(|ctr| ctr.gen::<u128>());
+        assert_eq!(rng.reseed_count(), 1);
+        rng.with(|ctr| ctr.gen::<u128>());
+        assert_eq!(rng.reseed_count(), 1);
+        rng.with(|ctr| ctr.next_u64());
+        assert_eq!(rng.reseed_count(), 2);
+    }
+
+    #[test]
+    fn test_keccak_clone_send_sync() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        let mut k = Keccak::new(&BitStream::from_bytes(b"twenty-five-characters ! "));
+        let copy = k.clone();
+        assert_send_sync(&k);
+        k.keccak(18);
+        assert_ne!(k.get_state(), copy.get_state());
+        let mut moved = std::thread::spawn(move || copy).join().unwrap();
+        moved.keccak(18);
+        assert_eq!(k.get_state(), moved.get_state());
+    }
+
+    #[test]
+    fn test_keccak_sponge_absorb() {
+        let mut message = BitStream::from_u64(&[1, 2, 3, 4, 5, 6]);
+        message.truncate(300);
+        let mut sponge = Keccak::new_sized(&BitStream::new(0), 8);
+        sponge.sponge_absorb(&mut message);
+        assert_eq!(message.len(), 400);
+
+        let mut expected = Keccak::new_sized(&message.slice(..200).to_bit_stream(), 8);
+        expected.keccak(18);
+        let mixed = &expected.get_state() ^ &message.slice(200..).to_bit_stream();
+        let mut expected = Keccak::new_sized(&mixed, 8);
+        expected.keccak(18);
+        assert_eq!(sponge.get_state(), expected.get_state());
+    }
+
+    #[test]
+    fn test_keccak_reset() {
+        let mut k = Keccak::new(&BitStream::from_bytes(b"twenty-five-characters ! "));
+        k.keccak(18);
+        k.reset();
+        assert_eq!(k.depth(), 8);
+        assert_eq!(k.get_state(), BitStream::new(200));
+        for idx in 0..200 {
+            assert_eq!(k.state1.get(idx), 0);
+        }
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        return bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    }
+
+    #[test]
+    fn test_keccak_f1600_zero_state() {
+        let mut k = KeccakF1600::new();
+        k.permute();
+        assert_eq!(k.lane(0, 0), 0xF1258F7940E1DDE7);
+        assert_eq!(k.lane(1, 0), 0x84D5CCF933C0478A);
+        assert_eq!(k.byte(0), 0xE7);
+    }
+
+    #[test]
+    fn test_keccak_f_rounds() {
+        let mut lanes = [0_u32; 25];
+        lanes[7] = 0xdeadbeef;
+        let mut full = KeccakF800::from_lanes(lanes);
+        let mut rounds = KeccakF800::from_lanes(lanes);
+        assert_eq!(KeccakF800::ROUNDS, 22);
+        full.permute();
+        rounds.permute_rounds(12);
+        assert_ne!(rounds, full);
+        let mut rounds = KeccakF800::from_lanes(lanes);
+        rounds.permute_rounds(0);
+        assert_eq!(rounds.lanes(), &lanes);
+        for round in 0..22 {
+            rounds.round(round);
+        }
+        assert_eq!(rounds, full);
+        assert_eq!(KeccakF200::ROUNDS, 18);
+        assert_eq!(KeccakF200::BYTES, 25);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_keccak_f_too_many_rounds() {
+        KeccakF::<u16>::new().permute_rounds(21);
+    }
+
+    #[test]
+    fn test_try_rounds() {
+        let too_many = Error::TooManyRounds {
+            rounds: 21,
+            max: 20,
+        };
+        assert_eq!(
+            KeccakF::<u16>::new().try_permute_rounds(21),
+            Err(too_many.clone())
+        );
+        assert_eq!(
+            Sponge::<u16>::try_with_rounds(10, 21)
+                .unwrap_err()
+                .to_string(),
+            too_many.to_string()
+        );
+        assert!(Sponge::<u16>::try_with_rounds(50, 20).is_err());
+        let mut k = Keccak::new_sized(&BitStream::from_u64(&[3]), 16);
+        assert_eq!(k.try_keccak(21), Err(too_many));
+        assert_eq!(k.try_keccak(20), Ok(()));
+        assert_eq!(
+            KeccakCtr::try_new(&BitStream::from_u64(&[7]), 12, 4).err(),
+            Some(Error::InvalidDepth { depth: 12 })
+        );
+    }
+
+    #[test]
+    fn test_keccak_f_bytes() {
+        let mut k = KeccakF::<u16>::new();
+        k.xor_bytes(3, &[0x12, 0x34, 0x56]);
+        assert_eq!(k.lane(1, 0), 0x1200);
+        assert_eq!(k.lane(2, 0), 0x5634);
+        let mut out = [0_u8; 5];
+        k.extract_bytes(1, &mut out);
+        assert_eq!(out, [0, 0, 0x12, 0x34, 0x56]);
+        k.reset();
+        assert_eq!(k, KeccakF::new());
+    }
+
+    #[test]
+    fn test_sponge_sha3() {
+        // SHA3-256 and SHAKE128 over keccak-f[1600], rates of 136 and 168 bytes
+        let mut sponge = Sponge::<u64>::new(136);
+        sponge.finish(0x06);
+        let mut digest = [0_u8; 32];
+        sponge.squeeze(&mut digest);
+        assert_eq!(
+            hex(&digest),
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+        );
+
+        sponge.reset();
+        sponge.absorb(b"a");
+        sponge.absorb(b"bc");
+        sponge.finish(0x06);
+        sponge.squeeze(&mut digest);
+        assert_eq!(
+            hex(&digest),
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+        );
+
+        let mut shake = Sponge::<u64>::new(168);
+        shake.finish(0x1F);
+        let mut out = [0_u8; 32];
+        shake.squeeze(&mut out[..5]);
+        shake.squeeze(&mut out[5..]);
+        assert_eq!(
+            hex(&out),
+            "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"
+        );
+
+        // message longer than the rate, SHA3-512 has a rate of 72 bytes
+        let mut sponge = Sponge::<u64>::new(72);
+        sponge.absorb(&[b'a'; 200]);
+        sponge.finish(0x06);
+        let mut digest = [0_u8; 64];
+        sponge.squeeze(&mut digest);
+        assert_eq!(
+            hex(&digest),
+            "eae6c85c6904f11075de9f9d5e1064371d000510fa3d2d79d40cf9be34892fb0\
+             1859d0a0234e138bcb0ad5c84f6c0dca226a414b0c9a2897cb695f5185fe36ec"
+        );
+    }
+
+    /// within 1e-9 of a reference value computed at high precision
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} isn't {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_stats_special_functions() {
+        use crate::stats::{erfc, igamc};
+        assert_close(igamc(0.5, 0.1), 0.654720846018577);
+        assert_close(igamc(1.5, 2.441302), 0.180598073453997);
+        assert_close(igamc(3.0, 0.5), 0.985612322033029);
+        assert_close(igamc(4.5, 20.0), 7.59852522946428e-6);
+        assert_close(igamc(48.5, 49.0), 0.452480388960377);
+        assert_close(igamc(2048.0, 2100.0), 0.125728969029663);
+        assert_close(erfc(0.0), 1.0);
+        assert_close(erfc(0.3), 0.671373240540873);
+        assert_close(erfc(1.0), 0.157299207050285);
+        assert_close(erfc(-0.7), 1.677801193837418);
+        assert_close(erfc(4.5), 1.96616044154289e-10);
+    }
+
+    /// the first 100 bits of pi in binary, used by most of SP 800-22's
+    /// worked examples
+    const PI_100: &str = "1100100100001111110110101010001000100001011010001100001000110100110001001100011001100010100010111000";
+
+    fn e_bits(len: usize) -> BitStream {
+        let path = format!("{}/tests/vectors/e.hex", env!("CARGO_MANIFEST_DIR"));
+        let mut bits = BitStream::from_hex(&std::fs::read_to_string(path).unwrap()).unwrap();
+        bits.truncate(len);
+        return bits;
+    }
+
+    /// the worked examples from section 2 of SP 800-22, to the six digits
+    /// it gives
+    #[test]
+    fn test_stats_examples() {
+        use crate::stats::*;
+        let bits = |src: &str| BitStream::from_bin_str(src).unwrap();
+        let pi = bits(PI_100);
+        let close = |actual: f64, expected: f64| {
+            assert!(
+                (actual - expected).abs() < 5e-7,
+                "{} isn't {}",
+                actual,
+                expected
+            );
+        };
+        close(frequency(&bits("1011010101")).unwrap(), 0.527089);
+        close(frequency(&pi).unwrap(), 0.109599);
+        close(block_frequency(&bits("0110011010"), 3).unwrap(), 0.801252);
+        close(block_frequency(&pi, 10).unwrap(), 0.706438);
+        close(runs(&bits("1001101011")).unwrap(), 0.147232);
+        close(runs(&pi).unwrap(), 0.500798);
+        let longest = "11001100000101010110110001001100111000000000001001001101010100010001001111010110100000001101011111001100111001101101100010110010";
+        close(longest_run(&bits(longest)).unwrap(), 0.180598);
+        close(rank(&e_bits(100_000)).unwrap(), 0.532069);
+        let (first, second) = serial(&bits("0011011101"), 3).unwrap();
+        close(first, 0.808792);
+        close(second, 0.670320);
+        close(
+            approximate_entropy(&bits("0100110101"), 3).unwrap(),
+            0.261961,
+        );
+        close(approximate_entropy(&pi, 2).unwrap(), 0.235301);
+        close(
+            cumulative_sums(&bits("1011010111"), false).unwrap(),
+            0.411659,
+        );
+        close(cumulative_sums(&pi, false).unwrap(), 0.219194);
+        close(cumulative_sums(&pi, true).unwrap(), 0.114866);
+        let (low, high) = proportion_range(ALPHA, 1000);
+        close(low, 0.99 - 0.0094392);
+        close(high, 0.99 + 0.0094392);
+    }
+
+    /// SP 800-22's DFT examples count one peak fewer than its definition
+    /// gives, eg. |S_0| .. |S_4| for 1001010011 are 0, 2, 4.47, 2 and 4.47,
+    /// all under the threshold of 5.47, where it counts 4 of the 5. These
+    /// values follow the definition, at lengths that are and aren't powers
+    /// of two.
+    #[test]
+    fn test_stats_dft() {
+        use crate::stats::dft;
+        let bits = BitStream::from_bin_str("1001010011").unwrap();
+        assert_close(dft(&bits).unwrap(), 0.468159909854428);
+        assert_close(
+            dft(&BitStream::from_bin_str(PI_100).unwrap()).unwrap(),
+            0.646355195539490,
+        );
+        assert_close(dft(&e_bits(1000)).unwrap(), 0.561657715013042);
+        assert_close(dft(&e_bits(1024)).unwrap(), 0.207025608156935);
+        assert_close(dft(&e_bits(4097)).unwrap(), 0.894498039910115);
+    }
+
+    #[test]
+    fn test_stats_more_bits() {
+        use crate::stats::*;
+        // the 128 bit blocks longest_run switches to from 6272 bits
+        assert_close(longest_run(&e_bits(10_000)).unwrap(), 0.945250820502373);
+        let (first, second) = serial(&e_bits(4096), 5).unwrap();
+        assert_close(first, 0.861270896244429);
+        assert_close(second, 0.930121276098784);
+        assert_close(
+            approximate_entropy(&e_bits(4096), 4).unwrap(),
+            0.856360494232269,
+        );
+        let evenly: Vec<f64> = (0..100).map(|idx| idx as f64 / 100.0).collect();
+        assert_close(uniformity(&evenly).unwrap(), 1.0);
+        assert!(uniformity(&[0.5; 100]).unwrap() < 0.0001);
+    }
+
+    #[test]
+    fn test_stats_errors() {
+        use crate::stats::*;
+        let empty = BitStream::new(0);
+        assert_eq!(frequency(&empty), Err(Error::TooShort { len: 0, min: 1 }));
+        assert_eq!(
+            longest_run(&BitStream::new(127)),
+            Err(Error::TooShort { len: 127, min: 128 })
+        );
+        assert_eq!(
+            rank(&BitStream::new(1023)),
+            Err(Error::TooShort {
+                len: 1023,
+                min: 1024
+            })
+        );
+        let bits = BitStream::new(64);
+        assert_eq!(
+            block_frequency(&bits, 0),
+            Err(Error::InvalidBlockLength { len: 0 })
+        );
+        assert_eq!(
+            block_frequency(&bits, 65),
+            Err(Error::InvalidBlockLength { len: 65 })
+        );
+        assert_eq!(serial(&bits, 1), Err(Error::InvalidBlockLength { len: 1 }));
+        assert_eq!(serial(&bits, 7), Err(Error::InvalidBlockLength { len: 7 }));
+        assert_eq!(
+            approximate_entropy(&bits, 0),
+            Err(Error::InvalidBlockLength { len: 0 })
+        );
+        assert!(serial(&bits, 6).is_ok());
+        assert_eq!(uniformity(&[]), Err(Error::TooShort { len: 0, min: 1 }));
+        // all zeros fail the runs prerequisite
+        assert_eq!(runs(&BitStream::new(100)), Ok(0.0));
+    }
 }
@@ -0,0 +1,202 @@
+//! serde support, enabled with the `serde` feature.
+//! BitStream serializes as its bit length and the bits packed MSB-first into
+//! bytes, written as a hex string in human-readable formats.
+
+use crate::{BitStream, Error, KeccakSnapshot};
+use alloc::{format, string::String, vec::Vec};
+use core::{
+    convert::TryFrom,
+    fmt::{self, Formatter},
+};
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+const FIELDS: &[&str] = &["length", "bits"];
+
+fn from_packed<E: de::Error>(length: usize, bytes: &[u8]) -> Result<BitStream, E> {
+    let temp = BitStream::from_partial_bytes(bytes, length)
+        .map_err(|_| E::invalid_length(bytes.len(), &"ceil(length / 8) bytes of bits"))?;
+    // from_partial_bytes clears the bits past length, but only one encoding
+    // of each stream is accepted, like Keccak::restore
+    if !length.is_multiple_of(8) && bytes[bytes.len() - 1] << (length % 8) != 0 {
+        return Err(E::invalid_value(
+            de::Unexpected::Bytes(bytes),
+            &"zero bits after the last bit of length",
+        ));
+    }
+    return Ok(temp);
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut temp = String::with_capacity(2 * bytes.len());
+    for byte in bytes {
+        temp.push(DIGITS[(byte >> 4) as usize] as char);
+        temp.push(DIGITS[(byte & 0xF) as usize] as char);
+    }
+    return temp;
+}
+
+fn from_hex<E: de::Error>(src: &str) -> Result<Vec<u8>, E> {
+    if !src.len().is_multiple_of(2) {
+        return Err(E::invalid_length(
+            src.len(),
+            &"an even number of hex digits",
+        ));
+    }
+    // from_str_radix alone would also take a sign, eg. "+1"
+    if !src.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(E::invalid_value(de::Unexpected::Str(src), &"hex digits"));
+    }
+    let mut temp = Vec::with_capacity(src.len() / 2);
+    for pair in src.as_bytes().chunks(2) {
+        match u8::from_str_radix(core::str::from_utf8(pair).unwrap_or("?"), 16) {
+            Ok(byte) => temp.push(byte),
+            Err(_) => return Err(E::invalid_value(de::Unexpected::Str(src), &"hex digits")),
+        }
+    }
+    return Ok(temp);
+}
+
+/// serializes as a byte string in binary formats
+struct Bytes<'a>(&'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_bytes(self.0);
+    }
+}
+
+/// accepts a hex string, a byte string or a sequence of bytes
+struct ByteBuf(Vec<u8>);
+
+struct ByteBufVisitor;
+
+impl<'de> Visitor<'de> for ByteBufVisitor {
+    type Value = ByteBuf;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        return f.write_str("hex string or bytes");
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<ByteBuf, E> {
+        return Ok(ByteBuf(from_hex(v)?));
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<ByteBuf, E> {
+        return Ok(ByteBuf(v.to_vec()));
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<ByteBuf, E> {
+        return Ok(ByteBuf(v));
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ByteBuf, A::Error> {
+        let mut temp = Vec::new();
+        while let Some(byte) = seq.next_element()? {
+            temp.push(byte);
+        }
+        return Ok(ByteBuf(temp));
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            return deserializer.deserialize_str(ByteBufVisitor);
+        }
+        return deserializer.deserialize_byte_buf(ByteBufVisitor);
+    }
+}
+
+impl Serialize for BitStream {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let human_readable = serializer.is_human_readable();
+        let bytes = self.to_bytes();
+        let mut state = serializer.serialize_struct("BitStream", 2)?;
+        state.serialize_field("length", &self.len())?;
+        if human_readable {
+            state.serialize_field("bits", &to_hex(&bytes))?;
+        } else {
+            state.serialize_field("bits", &Bytes(&bytes))?;
+        }
+        return state.end();
+    }
+}
+
+struct BitStreamVisitor;
+
+impl<'de> Visitor<'de> for BitStreamVisitor {
+    type Value = BitStream;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        return f.write_str("struct BitStream");
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<BitStream, A::Error> {
+        let length: usize = match seq.next_element()? {
+            Some(val) => val,
+            None => return Err(de::Error::invalid_length(0, &self)),
+        };
+        let bits: ByteBuf = match seq.next_element()? {
+            Some(val) => val,
+            None => return Err(de::Error::invalid_length(1, &self)),
+        };
+        return from_packed(length, &bits.0);
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<BitStream, A::Error> {
+        let mut length: Option<usize> = None;
+        let mut bits: Option<ByteBuf> = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "length" if length.is_none() => length = Some(map.next_value()?),
+                "bits" if bits.is_none() => bits = Some(map.next_value()?),
+                "length" | "bits" => {
+                    return Err(de::Error::custom(format!("duplicate field `{}`", key)))
+                }
+                _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+            }
+        }
+        let length = match length {
+            Some(val) => val,
+            None => return Err(de::Error::missing_field("length")),
+        };
+        let bits = match bits {
+            Some(val) => val,
+            None => return Err(de::Error::missing_field("bits")),
+        };
+        return from_packed(length, &bits.0);
+    }
+}
+
+impl<'de> Deserialize<'de> for BitStream {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        return deserializer.deserialize_struct("BitStream", FIELDS, BitStreamVisitor);
+    }
+}
+
+/// KeccakSnapshot's fields before they're checked, so invalid depths from
+/// untrusted input never reach Keccak
+#[derive(Deserialize)]
+#[serde(rename = "KeccakSnapshot")]
+pub(crate) struct RawSnapshot {
+    depth: usize,
+    state: BitStream,
+}
+
+impl TryFrom<RawSnapshot> for KeccakSnapshot {
+    type Error = Error;
+
+    fn try_from(raw: RawSnapshot) -> Result<Self, Error> {
+        let temp = KeccakSnapshot {
+            depth: raw.depth,
+            state: raw.state,
+        };
+        temp.check()?;
+        return Ok(temp);
+    }
+}
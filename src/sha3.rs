@@ -0,0 +1,60 @@
+//! the FIPS 202 hash and extendable-output functions, one-shot wrappers over
+//! Sponge<u64> that need no allocation. For incremental input use a Sponge
+//! with the same rate and suffix.
+
+use crate::sponge::Sponge;
+
+/// delimited suffix of the SHA3 hashes
+pub const SHA3_SUFFIX: u8 = 0x06;
+/// delimited suffix of the SHAKE XOFs
+pub const SHAKE_SUFFIX: u8 = 0x1F;
+/// delimited suffix of the original Keccak submission, as still used by
+/// eg. Ethereum's Keccak-256
+pub const KECCAK_SUFFIX: u8 = 0x01;
+
+/// rate in bytes of a hash with a digest of size bytes, 200 - 2 * size
+const fn rate(size: usize) -> usize {
+    return 200 - 2 * size;
+}
+
+fn hash<const N: usize>(input: &[u8]) -> [u8; N] {
+    let mut sponge = Sponge::<u64>::new(rate(N));
+    sponge.absorb(input);
+    sponge.finish(SHA3_SUFFIX);
+    let mut temp = [0; N];
+    sponge.squeeze(&mut temp);
+    return temp;
+}
+
+fn xof(security: usize, input: &[u8], dest: &mut [u8]) {
+    let mut sponge = Sponge::<u64>::new(rate(security / 8));
+    sponge.absorb(input);
+    sponge.finish(SHAKE_SUFFIX);
+    sponge.squeeze(dest);
+}
+
+pub fn sha3_224(input: &[u8]) -> [u8; 28] {
+    return hash(input);
+}
+
+pub fn sha3_256(input: &[u8]) -> [u8; 32] {
+    return hash(input);
+}
+
+pub fn sha3_384(input: &[u8]) -> [u8; 48] {
+    return hash(input);
+}
+
+pub fn sha3_512(input: &[u8]) -> [u8; 64] {
+    return hash(input);
+}
+
+/// fills dest with SHAKE128 output, any length
+pub fn shake128(input: &[u8], dest: &mut [u8]) {
+    xof(128, input, dest);
+}
+
+/// fills dest with SHAKE256 output, any length
+pub fn shake256(input: &[u8], dest: &mut [u8]) {
+    xof(256, input, dest);
+}
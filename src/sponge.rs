@@ -0,0 +1,110 @@
+//! sponge construction over the array backed KeccakF, needs no allocation.
+
+use crate::{
+    keccak_f::{KeccakF, Lane},
+    Error,
+};
+
+/// rate is in bytes, capacity is the rest of the state. Input is absorbed
+/// byte at a time with pad10*1 padding, after the message is closed with a
+/// domain separation suffix given as in the Keccak team's "delimited suffix"
+/// convention: the suffix bits LSB-first followed by a 1 bit, eg. 0x06 for
+/// SHA3, 0x1F for SHAKE and 0x01 for the original Keccak.
+#[derive(Clone, Debug)]
+pub struct Sponge<L: Lane> {
+    state: KeccakF<L>,
+    rate: usize,
+    rounds: usize,
+    offset: usize,
+    squeezing: bool,
+}
+
+impl<L: Lane> Sponge<L> {
+    /// sponge over the full keccak-f permutation
+    pub fn new(rate: usize) -> Self {
+        return Sponge::with_rounds(rate, KeccakF::<L>::ROUNDS);
+    }
+
+    /// sponge over keccak-p with the given number of rounds
+    pub fn with_rounds(rate: usize, rounds: usize) -> Self {
+        return match Sponge::try_with_rounds(rate, rounds) {
+            Ok(sponge) => sponge,
+            Err(err) => panic!("{}", err),
+        };
+    }
+
+    /// like with_rounds, but a rate that leaves no capacity or too many
+    /// rounds is an error instead of a panic
+    pub fn try_with_rounds(rate: usize, rounds: usize) -> Result<Self, Error> {
+        if rate == 0 || rate >= KeccakF::<L>::BYTES {
+            return Err(Error::InvalidRate { rate });
+        }
+        if rounds > KeccakF::<L>::ROUNDS {
+            return Err(Error::TooManyRounds {
+                rounds: rounds as u64,
+                max: KeccakF::<L>::ROUNDS as u64,
+            });
+        }
+        return Ok(Sponge {
+            state: KeccakF::new(),
+            rate,
+            rounds,
+            offset: 0,
+            squeezing: false,
+        });
+    }
+
+    pub fn rate(&self) -> usize {
+        return self.rate;
+    }
+
+    pub fn state(&self) -> &KeccakF<L> {
+        return &self.state;
+    }
+
+    pub fn absorb(&mut self, mut input: &[u8]) {
+        assert!(!self.squeezing, "can't absorb after squeezing has started");
+        while !input.is_empty() {
+            let take = (self.rate - self.offset).min(input.len());
+            self.state.xor_bytes(self.offset, &input[..take]);
+            self.offset += take;
+            input = &input[take..];
+            if self.offset == self.rate {
+                self.state.permute_rounds(self.rounds);
+                self.offset = 0;
+            }
+        }
+    }
+
+    /// pads the absorbed message, suffix is the delimited domain suffix
+    pub fn finish(&mut self, suffix: u8) {
+        assert!(!self.squeezing, "sponge has already been finished");
+        self.state.xor_bytes(self.offset, &[suffix]);
+        self.state.xor_bytes(self.rate - 1, &[0x80]);
+        self.state.permute_rounds(self.rounds);
+        self.offset = 0;
+        self.squeezing = true;
+    }
+
+    /// fills dest with output, can be called repeatedly for more
+    pub fn squeeze(&mut self, mut dest: &mut [u8]) {
+        assert!(self.squeezing, "finish must be called before squeezing");
+        while !dest.is_empty() {
+            if self.offset == self.rate {
+                self.state.permute_rounds(self.rounds);
+                self.offset = 0;
+            }
+            let take = (self.rate - self.offset).min(dest.len());
+            self.state.extract_bytes(self.offset, &mut dest[..take]);
+            self.offset += take;
+            dest = &mut dest[take..];
+        }
+    }
+
+    /// back to the empty state, wiping the old one
+    pub fn reset(&mut self) {
+        self.state.reset();
+        self.offset = 0;
+        self.squeezing = false;
+    }
+}
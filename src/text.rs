@@ -0,0 +1,215 @@
+//! text formats for BitStream. Display and Binary write one '0' or '1' per
+//! bit, LowerHex and UpperHex write ceil(len / 4) digits with a partial last
+//! digit padded with zero bits, and base64 encodes the bytes of to_bytes.
+//! The alternate flag adds a 0b or 0x prefix. Parsing skips ASCII
+//! whitespace, so vectors split over lines can be pasted as they are.
+
+use crate::{BitOrder, BitStream, Error};
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::{self, Binary, Display, Formatter, LowerHex, UpperHex, Write},
+    str::FromStr,
+};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl BitStream {
+    /// hex digits, 4 bits each, so the length is 4 times the digit count.
+    /// An optional 0x prefix is skipped.
+    pub fn from_hex(src: &str) -> Result<Self, Error> {
+        let (offset, digits) = strip_prefix(src, "0x");
+        let mut bytes = Vec::with_capacity(digits.len().div_ceil(2));
+        let mut count = 0;
+        for (idx, c) in digits.char_indices() {
+            if c.is_ascii_whitespace() {
+                continue;
+            }
+            let nibble = match c.to_digit(16) {
+                Some(nibble) => nibble as u8,
+                None => {
+                    return Err(Error::Parse {
+                        index: offset + idx,
+                    })
+                }
+            };
+            if count % 2 == 0 {
+                bytes.push(nibble << 4);
+            } else {
+                *bytes.last_mut().unwrap() |= nibble;
+            }
+            count += 1;
+        }
+        let mut temp = BitStream::from(bytes);
+        temp.truncate(4 * count);
+        return Ok(temp);
+    }
+
+    /// '0' and '1' characters, one bit each. An optional 0b prefix is
+    /// skipped.
+    pub fn from_bin_str(src: &str) -> Result<Self, Error> {
+        return BitStream::from_bin_str_with_order(src, BitOrder::MsbFirst);
+    }
+
+    /// from_bin_str with every 8 digits read as a byte written most
+    /// significant bit first, as from_bytes_with_order takes bytes. With
+    /// LsbFirst the digits of each byte, and of a partial last one, are
+    /// reversed, so "01100001" is the same stream as b"a" in that order.
+    pub fn from_bin_str_with_order(src: &str, order: BitOrder) -> Result<Self, Error> {
+        let (offset, digits) = strip_prefix(src, "0b");
+        let mut temp = BitStream::new(0);
+        let mut byte = Vec::with_capacity(8);
+        for (idx, c) in digits.char_indices() {
+            match c {
+                '0' => byte.push(0),
+                '1' => byte.push(1),
+                _ if c.is_ascii_whitespace() => continue,
+                _ => {
+                    return Err(Error::Parse {
+                        index: offset + idx,
+                    })
+                }
+            }
+            if byte.len() == 8 {
+                push_byte(&mut temp, &mut byte, order);
+            }
+        }
+        push_byte(&mut temp, &mut byte, order);
+        return Ok(temp);
+    }
+
+    /// standard base64 with or without = padding. It only holds whole bytes,
+    /// so the length is always a multiple of 8.
+    pub fn from_base64(src: &str) -> Result<Self, Error> {
+        let mut bytes = Vec::with_capacity(src.len() / 4 * 3 + 2);
+        let mut acc: u32 = 0;
+        let mut count = 0;
+        let mut padding = 0;
+        for (idx, c) in src.char_indices() {
+            if c.is_ascii_whitespace() {
+                continue;
+            }
+            if c == '=' {
+                padding += 1;
+                continue;
+            }
+            let val = match BASE64.iter().position(|&digit| digit as char == c) {
+                Some(val) if padding == 0 => val as u32,
+                _ => return Err(Error::Parse { index: idx }),
+            };
+            acc = (acc << 6) | val;
+            count += 1;
+            if count % 4 == 0 {
+                bytes.extend_from_slice(&acc.to_be_bytes()[1..]);
+                acc = 0;
+            }
+        }
+        match count % 4 {
+            0 => {}
+            1 => return Err(Error::Parse { index: src.len() }),
+            rem => {
+                let tail = (acc << (6 * (4 - rem))).to_be_bytes();
+                bytes.extend_from_slice(&tail[1..rem]);
+            }
+        }
+        if padding > 2 || (padding > 0 && (count + padding) % 4 != 0) {
+            return Err(Error::Parse { index: src.len() });
+        }
+        return Ok(BitStream::from(bytes));
+    }
+
+    /// standard base64 with = padding, a partial last byte is padded with
+    /// zero bits first
+    pub fn to_base64(&self) -> String {
+        let bytes = self.to_bytes();
+        let mut temp = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for chunk in bytes.chunks(3) {
+            let mut group = [0_u8; 4];
+            group[1..=chunk.len()].copy_from_slice(chunk);
+            let acc = u32::from_be_bytes(group);
+            for idx in 0..4 {
+                if idx <= chunk.len() {
+                    temp.push(BASE64[(acc >> (18 - 6 * idx) & 0x3F) as usize] as char);
+                } else {
+                    temp.push('=');
+                }
+            }
+        }
+        return temp;
+    }
+
+    fn write_hex(&self, f: &mut Formatter<'_>, digits: &[u8; 16]) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        for (idx, byte) in self.bytes().enumerate() {
+            f.write_char(digits[(byte >> 4) as usize] as char)?;
+            if 8 * idx + 4 < self.len() {
+                f.write_char(digits[(byte & 0xF) as usize] as char)?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// the offset of the rest of src after prefix, and the rest
+fn strip_prefix<'a>(src: &'a str, prefix: &str) -> (usize, &'a str) {
+    let trimmed = src.trim_start();
+    let skipped = src.len() - trimmed.len();
+    return match trimmed.strip_prefix(prefix) {
+        Some(rest) => (skipped + prefix.len(), rest),
+        None => (skipped, trimmed),
+    };
+}
+
+/// moves the digits of byte onto the end of bits, last digit first for
+/// LsbFirst
+fn push_byte(bits: &mut BitStream, byte: &mut Vec<u8>, order: BitOrder) {
+    if order == BitOrder::LsbFirst {
+        byte.reverse();
+    }
+    for &bit in byte.iter() {
+        bits.push(bit);
+    }
+    byte.clear();
+}
+
+/// one '0' or '1' per bit, the inverse of FromStr
+impl Display for BitStream {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for bit in self {
+            f.write_char(if bit == 1 { '1' } else { '0' })?;
+        }
+        return Ok(());
+    }
+}
+
+impl Binary for BitStream {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0b")?;
+        }
+        return Display::fmt(self, f);
+    }
+}
+
+impl LowerHex for BitStream {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        return self.write_hex(f, b"0123456789abcdef");
+    }
+}
+
+impl UpperHex for BitStream {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        return self.write_hex(f, b"0123456789ABCDEF");
+    }
+}
+
+/// parses the '0' and '1' characters Display writes, see from_bin_str.
+/// Use from_bytes for the bits of a string's utf-8 bytes.
+impl FromStr for BitStream {
+    type Err = Error;
+
+    fn from_str(src: &str) -> Result<Self, Error> {
+        return BitStream::from_bin_str(src);
+    }
+}
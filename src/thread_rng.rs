@@ -0,0 +1,116 @@
+use crate::{BitStream, KeccakCtr, Random};
+use std::{cell::RefCell, process, rc::Rc};
+
+/// keccak-f[1600] at full strength
+const DEPTH: usize = 64;
+const ROUNDS: u64 = 24;
+/// bytes of OS entropy used for seeding and for each reseed
+const SEED_LEN: usize = 32;
+/// number of u64 drawn before fresh OS entropy is mixed in
+const RESEED_INTERVAL: u64 = 1 << 16;
+
+thread_local! {
+    static THREAD_RNG: Rc<RefCell<ReseedingCtr>> =
+        Rc::new(RefCell::new(ReseedingCtr::new(RESEED_INTERVAL)));
+}
+
+/// KeccakCtr that reseeds itself from OS entropy every `interval` draws,
+/// and before the first draw in a process forked after it was seeded, so
+/// parent and child don't continue the same stream.
+pub(crate) struct ReseedingCtr {
+    ctr: KeccakCtr,
+    interval: u64,
+    remaining: u64,
+    pid: u32,
+}
+
+fn os_seed() -> BitStream {
+    let mut bytes = [0_u8; SEED_LEN];
+    if let Err(err) = getrandom::getrandom(&mut bytes) {
+        panic!("could not get entropy from the OS: {}", err);
+    }
+    return BitStream::from_val(&bytes);
+}
+
+impl ReseedingCtr {
+    pub(crate) fn new(interval: u64) -> Self {
+        return ReseedingCtr {
+            ctr: KeccakCtr::new(&os_seed(), DEPTH, ROUNDS),
+            interval,
+            remaining: interval,
+            pid: process::id(),
+        };
+    }
+
+    pub(crate) fn reseed(&mut self) {
+        self.ctr.reseed(&os_seed());
+        self.remaining = self.interval;
+        self.pid = process::id();
+    }
+
+    /// gives f the generator, reseeding first if the interval has run out
+    pub(crate) fn with<T, F: FnOnce(&mut KeccakCtr) -> T>(&mut self, f: F) -> T {
+        if self.remaining == 0 || self.pid != process::id() {
+            self.reseed();
+        }
+        let start = self.ctr.position();
+        let val = f(&mut self.ctr);
+        let drawn = self.ctr.position().wrapping_sub(start);
+        self.remaining = self.remaining.saturating_sub(drawn);
+        return val;
+    }
+
+    pub(crate) fn reseed_count(&self) -> u64 {
+        return self.ctr.reseed_count();
+    }
+}
+
+/// handle to the calling thread's generator, returned by thread_rng.
+/// Cheap to clone, but can't be sent to other threads.
+#[derive(Clone)]
+pub struct ThreadRng {
+    inner: Rc<RefCell<ReseedingCtr>>,
+}
+
+/// the calling thread's generator, seeded from OS entropy the first time it
+/// is used on each thread and reseeded every 2^16 draws and after a fork.
+pub fn thread_rng() -> ThreadRng {
+    return ThreadRng {
+        inner: THREAD_RNG.with(|rng| rng.clone()),
+    };
+}
+
+/// draws a value from the calling thread's generator,
+/// eg. `rand_keccak::random::<u64>()`
+pub fn random<T: Random>() -> T {
+    return thread_rng().gen();
+}
+
+impl ThreadRng {
+    pub fn next_u64(&mut self) -> u64 {
+        return self.inner.borrow_mut().with(|ctr| ctr.next_u64());
+    }
+
+    pub fn gen<T: Random>(&mut self) -> T {
+        return self.inner.borrow_mut().with(|ctr| ctr.gen());
+    }
+
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.inner.borrow_mut().with(|ctr| {
+            for chunk in dest.chunks_mut(8) {
+                let bytes = ctr.next_u64().to_be_bytes();
+                chunk.copy_from_slice(&bytes[..chunk.len()]);
+            }
+        });
+    }
+
+    /// mixes fresh OS entropy into the generator now
+    pub fn reseed(&mut self) {
+        self.inner.borrow_mut().reseed();
+    }
+
+    /// number of times this thread's generator has been reseeded
+    pub fn reseed_count(&self) -> u64 {
+        return self.inner.borrow().reseed_count();
+    }
+}
@@ -1,66 +1,129 @@
 #![allow(dead_code)]
 
-use std::alloc::{alloc_zeroed, dealloc, Layout};
+use crate::wipe::wipe_bytes;
+use alloc::{boxed::Box, vec};
+use core::{mem::size_of_val, ptr, slice};
 
-/// look into using array::from_raw instead of raw pointer
-pub struct VLA<T> {
-    len: usize,
-    data: *mut T,
+/// fixed length heap array. get and set check bounds and panic, the
+/// unchecked variants are for inner loops whose indices are already known to
+/// be in range. Backed by a Box<[T]>, so zero lengths and zero sized types
+/// need no special casing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VLA<T: Copy> {
+    data: Box<[T]>,
 }
 
-impl<T: Copy> VLA<T> {
+impl<T: Copy + Default> VLA<T> {
+    /// length elements, all T::default()
     pub fn new(length: usize) -> Self {
-        unsafe {
-            let temp = VLA {
-                len: length,
-                data: alloc_zeroed(Layout::array::<T>(length).unwrap()) as *mut T,
-            };
-            if temp.data.is_null() {
-                panic!("allocation failed");
+        return VLA {
+            data: vec![T::default(); length].into_boxed_slice(),
+        };
+    }
+
+    /// sets every element to T::default() with writes that won't be
+    /// optimized away
+    pub fn zeroize(&mut self) {
+        for val in self.data.iter_mut() {
+            unsafe {
+                ptr::write_volatile(val, T::default());
             }
-            return temp;
         }
     }
+}
 
+impl<T: Copy> VLA<T> {
     pub fn get(&self, idx: usize) -> T {
-        //slowest part of function, but could have undefined dereference withou it
-        assert_eq!(idx < self.len, true);
-        return unsafe { *self.data.add(idx) };
+        if idx >= self.data.len() {
+            panic!(
+                "VLA index {} out of bounds for length {}",
+                idx,
+                self.data.len()
+            );
+        }
+        return self.data[idx];
     }
 
     pub fn set(&mut self, idx: usize, val: T) {
-        unsafe {
-            assert_eq!(idx < self.len, true);
-            *self.data.add(idx) = val;
+        if idx >= self.data.len() {
+            panic!(
+                "VLA index {} out of bounds for length {}",
+                idx,
+                self.data.len()
+            );
         }
+        self.data[idx] = val;
+    }
+
+    pub fn try_get(&self, idx: usize) -> Option<T> {
+        return self.data.get(idx).copied();
+    }
+
+    /// # Safety
+    /// idx must be less than len()
+    #[inline]
+    pub unsafe fn get_unchecked(&self, idx: usize) -> T {
+        debug_assert!(idx < self.data.len());
+        return *self.data.get_unchecked(idx);
+    }
+
+    /// # Safety
+    /// idx must be less than len()
+    #[inline]
+    pub unsafe fn set_unchecked(&mut self, idx: usize, val: T) {
+        debug_assert!(idx < self.data.len());
+        *self.data.get_unchecked_mut(idx) = val;
     }
 
     pub fn len(&self) -> usize {
-        return self.len;
+        return self.data.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.data.is_empty();
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        return &self.data;
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        return &mut self.data;
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        return self.data.iter();
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        return self.data.iter_mut();
     }
 }
 
-impl<T> Drop for VLA<T> {
+/// contents may be key material, wiped before the Box frees them. T: Copy
+/// means there is no drop glue that could observe the zeroed elements.
+impl<T: Copy> Drop for VLA<T> {
     fn drop(&mut self) {
         unsafe {
-            dealloc(self.data as *mut u8, Layout::array::<T>(self.len).unwrap());
+            wipe_bytes(self.data.as_mut_ptr() as *mut u8, size_of_val(&*self.data));
         }
     }
 }
 
-impl<T: PartialEq> PartialEq for VLA<T> {
-    fn eq(&self, other: &Self) -> bool {
-        if self.len != other.len {
-            return false;
-        };
+impl<'a, T: Copy> IntoIterator for &'a VLA<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
 
-        for idx in 0..self.len {
-            unsafe {
-                if *(self.data.add(idx)) != *(other.data.add(idx)) {
-                    return false;
-                };
-            }
-        }
-        return true;
+    fn into_iter(self) -> Self::IntoIter {
+        return self.iter();
+    }
+}
+
+impl<'a, T: Copy> IntoIterator for &'a mut VLA<T> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.iter_mut();
     }
 }
@@ -0,0 +1,38 @@
+//! overwrites memory that may hold key material with zeros, using volatile
+//! writes so the compiler can't remove them as dead stores before a free.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::{
+    ptr,
+    sync::atomic::{compiler_fence, Ordering},
+};
+
+/// zeroes len bytes starting at ptr.
+///
+/// # Safety
+/// ptr must be valid for writes of len bytes, and all zero bytes must be a
+/// valid value for whatever is stored there if it is read again.
+pub(crate) unsafe fn wipe_bytes(ptr: *mut u8, len: usize) {
+    for idx in 0..len {
+        ptr::write_volatile(ptr.add(idx), 0);
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(feature = "alloc")]
+/// zeroes the whole allocation of a Vec<u64>, including spare capacity
+/// that may still hold values from before a truncate
+pub(crate) fn wipe_vec_u64(words: &mut Vec<u64>) {
+    unsafe {
+        wipe_bytes(words.as_mut_ptr() as *mut u8, words.capacity() * 8);
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// same as wipe_vec_u64 for bytes
+pub(crate) fn wipe_vec_u8(bytes: &mut Vec<u8>) {
+    unsafe {
+        wipe_bytes(bytes.as_mut_ptr(), bytes.capacity());
+    }
+}
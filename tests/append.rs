@@ -0,0 +1,24 @@
+#![cfg(feature = "alloc")]
+
+use proptest::prelude::*;
+use rand_keccak::BitStream;
+
+mod common;
+
+proptest! {
+    #[test]
+    fn append_matches_push(a in common::bits(0..200), b in common::bits(0..200)) {
+        let mut pushed = BitStream::new(0);
+        pushed.extend_bits(a.iter().chain(b.iter()).copied());
+        let mut head = BitStream::new(0);
+        head.extend_bits(a.iter().copied());
+        let mut tail = BitStream::new(0);
+        tail.extend_bits(b.iter().copied());
+        prop_assert_eq!(head.concat(&tail), pushed);
+        head.append(&tail);
+        prop_assert_eq!(head.len(), a.len() + b.len());
+        for (idx, &bit) in a.iter().chain(b.iter()).enumerate() {
+            prop_assert_eq!(head.get(idx), bit);
+        }
+    }
+}
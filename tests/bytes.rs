@@ -0,0 +1,40 @@
+#![cfg(feature = "alloc")]
+
+use proptest::prelude::*;
+use rand_keccak::BitStream;
+
+mod common;
+
+proptest! {
+    #[test]
+    fn bytes_round_trip(bytes in common::bytes(0..100)) {
+        let bits = BitStream::from_bytes(&bytes);
+        prop_assert_eq!(bits.bit_len(), 8 * bytes.len());
+        prop_assert_eq!(bits.byte_len(), bytes.len());
+        prop_assert_eq!(bits.to_bytes(), bytes.clone());
+        prop_assert_eq!(BitStream::from(bytes.clone()), bits);
+    }
+
+    #[test]
+    fn partial_bytes_round_trip(
+        bytes in common::bytes(1..100),
+        unused in 0_usize..8,
+    ) {
+        let bit_len = 8 * bytes.len() - unused;
+        let bits = BitStream::from_partial_bytes(&bytes, bit_len).unwrap();
+        prop_assert_eq!(bits.bit_len(), bit_len);
+        for idx in 0..bit_len {
+            prop_assert_eq!(bits.get(idx), (bytes[idx / 8] >> (7 - idx % 8)) & 1);
+        }
+        let exported = bits.to_bytes();
+        let last = bytes.len() - 1;
+        prop_assert_eq!(&exported[..last], &bytes[..last]);
+        prop_assert_eq!(exported[last], bytes[last] & !((1_u16 << unused) - 1) as u8);
+        prop_assert_eq!(BitStream::from_partial_bytes(&exported, bit_len).unwrap(), bits);
+    }
+
+    #[test]
+    fn bytes_match_from_val(bytes in common::bytes(0..100)) {
+        prop_assert_eq!(BitStream::from_bytes(&bytes), BitStream::from_val(&bytes));
+    }
+}
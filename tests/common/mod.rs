@@ -0,0 +1,15 @@
+//! strategies shared by the property tests, each file uses only some
+#![allow(dead_code)]
+
+use core::ops::Range;
+use proptest::prelude::*;
+
+/// bytes of any value, with a length in len
+pub fn bytes(len: Range<usize>) -> impl Strategy<Value = Vec<u8>> {
+    return proptest::collection::vec(any::<u8>(), len);
+}
+
+/// single bits, 0 or 1, with a length in len
+pub fn bits(len: Range<usize>) -> impl Strategy<Value = Vec<u8>> {
+    return proptest::collection::vec(0_u8..2, len);
+}
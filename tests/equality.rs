@@ -0,0 +1,134 @@
+#![cfg(feature = "alloc")]
+
+use proptest::prelude::*;
+use rand_keccak::{BitOrder, BitStream};
+use std::{
+    collections::hash_map::DefaultHasher,
+    convert::TryInto,
+    hash::{Hash, Hasher},
+};
+
+fn hash(bits: &BitStream) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bits.hash(&mut hasher);
+    return hasher.finish();
+}
+
+/// the same bits built every way the crate offers
+fn constructions(bits: &[u8]) -> Vec<BitStream> {
+    let len = bits.len();
+    let mut packed = vec![0_u8; len.div_ceil(8)];
+    for (idx, &bit) in bits.iter().enumerate() {
+        packed[idx / 8] |= bit << (7 - idx % 8);
+    }
+    let text: String = bits
+        .iter()
+        .map(|&bit| if bit == 1 { '1' } else { '0' })
+        .collect();
+
+    let mut set = BitStream::new(len);
+    for (idx, &bit) in bits.iter().enumerate() {
+        set.set(idx, bit);
+    }
+    let mut pushed = BitStream::new(0);
+    for &bit in bits {
+        pushed.push(bit);
+    }
+    let (head, tail) = bits.split_at(len / 3);
+    let head: BitStream = head.iter().copied().collect();
+    let tail: BitStream = tail.iter().copied().collect();
+    let mut appended = head.clone();
+    appended.append(&tail);
+    // a longer stream with garbage past len, cut down
+    let mut truncated = BitStream::from_bytes(&packed);
+    truncated.add_val(&[u64::MAX]);
+    truncated.truncate(len);
+    let mut padded = BitStream::from_bytes(&packed);
+    padded.extend_bits([1, 1, 1, 1, 1, 1, 1, 1]);
+    let sliced = padded.slice(..len).to_bit_stream();
+    let mut rotated = BitStream::from_partial_bytes(&packed, len).unwrap();
+    rotated.rotate_left(5);
+    rotated.rotate_right(5);
+    let inverted = !&BitStream::from_bin_str(&text).unwrap();
+
+    let mut temp = vec![
+        set,
+        pushed,
+        appended,
+        head.concat(&tail),
+        &head + &tail,
+        truncated,
+        sliced,
+        rotated,
+        !inverted,
+        BitStream::from_partial_bytes(&packed, len).unwrap(),
+        BitStream::from_bin_str(&text).unwrap(),
+        text.parse().unwrap(),
+        bits.iter().copied().collect(),
+        bits.iter().map(|&bit| bit == 1).collect(),
+        BitStream::from_partial_bytes(&packed, len).unwrap().clone(),
+        &BitStream::from_partial_bytes(&packed, len).unwrap() ^ &BitStream::new(len),
+    ];
+    if len.is_multiple_of(8) {
+        temp.push(BitStream::from_bytes(&packed));
+        temp.push(BitStream::from_val(&packed));
+        temp.push(BitStream::try_from_val(&packed).unwrap());
+        temp.push(BitStream::from(packed.clone()));
+        temp.push(BitStream::from(&packed[..]));
+        temp.push(BitStream::from_base64(&BitStream::from_bytes(&packed).to_base64()).unwrap());
+        let reversed = BitStream::from_bytes_with_order(&packed, BitOrder::LsbFirst);
+        temp.push(
+            reversed
+                .convert_order(BitOrder::LsbFirst, BitOrder::MsbFirst)
+                .unwrap(),
+        );
+    }
+    if len.is_multiple_of(4) {
+        let hex = format!("{:x}", BitStream::from_bin_str(&text).unwrap());
+        temp.push(BitStream::from_hex(&hex).unwrap());
+    }
+    if len.is_multiple_of(64) {
+        let words: Vec<u64> = packed
+            .chunks(8)
+            .map(|chunk| u64::from_be_bytes(chunk.try_into().unwrap()))
+            .collect();
+        temp.push(BitStream::from_u64(&words));
+    }
+    return temp;
+}
+
+proptest! {
+    #[test]
+    fn constructors_agree(bits in proptest::collection::vec(0_u8..2, 0..200)) {
+        let all = constructions(&bits);
+        let first = &all[0];
+        for (idx, other) in all.iter().enumerate() {
+            prop_assert_eq!(other, first, "construction {}", idx);
+            prop_assert_eq!(hash(other), hash(first), "construction {}", idx);
+            prop_assert_eq!(other.cmp(first), std::cmp::Ordering::Equal);
+            // Debug shows the words as stored, so this checks they are
+            // normalized and not just compared as if they were
+            prop_assert_eq!(format!("{:?}", other), format!("{:?}", first));
+        }
+    }
+
+    #[test]
+    fn order_is_lexicographic(
+        a in proptest::collection::vec(0_u8..2, 0..150),
+        b in proptest::collection::vec(0_u8..2, 0..150),
+    ) {
+        let left: BitStream = a.iter().copied().collect();
+        let right: BitStream = b.iter().copied().collect();
+        prop_assert_eq!(left.cmp(&right), a.cmp(&b));
+        prop_assert_eq!(left == right, a == b);
+    }
+}
+
+#[test]
+fn zero_suffix_is_distinct() {
+    let short = BitStream::from_bin_str("10").unwrap();
+    let long = BitStream::from_bin_str("100").unwrap();
+    assert_ne!(short, long);
+    assert_ne!(hash(&short), hash(&long));
+    assert!(short < long);
+}
@@ -0,0 +1,48 @@
+//! fork the test process and check the child's thread_rng doesn't repeat
+//! the parent's stream. Kept in its own test binary so no other test threads
+//! are running when fork is called.
+#![cfg(all(unix, feature = "std"))]
+
+use rand_keccak::{random, thread_rng};
+use std::mem::size_of;
+
+/// runs f in a forked child and returns the u64 it wrote back over a pipe
+fn in_child(f: fn() -> u64) -> u64 {
+    let mut fds = [0 as libc::c_int; 2];
+    unsafe {
+        assert_eq!(libc::pipe(fds.as_mut_ptr()), 0);
+        let pid = libc::fork();
+        assert!(pid >= 0, "fork failed");
+        if pid == 0 {
+            let val = f().to_be_bytes();
+            libc::write(fds[1], val.as_ptr() as *const libc::c_void, val.len());
+            libc::_exit(0);
+        }
+        libc::close(fds[1]);
+        let mut val = [0_u8; size_of::<u64>()];
+        let read = libc::read(fds[0], val.as_mut_ptr() as *mut libc::c_void, val.len());
+        libc::close(fds[0]);
+        let mut status = 0;
+        libc::waitpid(pid, &mut status, 0);
+        assert_eq!(read, val.len() as isize, "child didn't report a value");
+        return u64::from_be_bytes(val);
+    }
+}
+
+#[test]
+fn test_fork_reseeds() {
+    // seed the parent's generator before forking so the child inherits it
+    random::<u64>();
+    let reseeds = thread_rng().reseed_count();
+
+    let child = in_child(random::<u64>);
+    let parent = random::<u64>();
+    assert_ne!(child, parent);
+
+    let child_reseeds = in_child(|| {
+        random::<u64>();
+        return thread_rng().reseed_count();
+    });
+    assert_eq!(child_reseeds, reseeds + 1);
+    assert_eq!(thread_rng().reseed_count(), reseeds);
+}
@@ -0,0 +1,21 @@
+#![cfg(feature = "alloc")]
+
+use proptest::prelude::*;
+use rand_keccak::BitStream;
+
+mod common;
+
+proptest! {
+    #[test]
+    fn iter_matches_get(bytes in common::bytes(0..40), skip in 0_usize..70) {
+        let bits = BitStream::from_bytes(&bytes);
+        let start = skip.min(bits.len());
+        let view = bits.slice(start..);
+        let iterated: Vec<u8> = view.iter().collect();
+        prop_assert_eq!(iterated.len(), view.len());
+        for (idx, &bit) in iterated.iter().enumerate() {
+            prop_assert_eq!(bit, bits.get(start + idx));
+        }
+        prop_assert_eq!(iterated.into_iter().collect::<BitStream>(), view.to_bit_stream());
+    }
+}
@@ -0,0 +1,563 @@
+//! known-answer tests from .rsp files in the NIST CAVP format, see
+//! tests/vectors/README.md. The files generated by tests/vectors/generated
+//! always run, the official CAVP files in tests/vectors/cavp are checked by
+//! the ignored cavp_ tests. Each check stops at the first mismatching case
+//! and reports its file, line and count.
+#![cfg(feature = "alloc")]
+
+use rand_keccak::{
+    k12, keccak_f::Lane, sha3_224, sha3_256, sha3_384, sha3_512, shake128, shake256, BitOrder,
+    BitStream, KangarooTwelve, Keccak, KeccakF,
+};
+use std::{fs, path::PathBuf};
+
+/// a blank line separated group of `Key = Value` lines, with the
+/// `[Key = Value]` headers in force where it appears
+struct Record {
+    file: String,
+    line: usize,
+    headers: Vec<(String, String)>,
+    fields: Vec<(String, String)>,
+}
+
+impl Record {
+    fn has(&self, key: &str) -> bool {
+        return self.fields.iter().any(|(k, _)| k == key);
+    }
+
+    fn get(&self, key: &str) -> &str {
+        return match self.fields.iter().find(|(k, _)| k == key) {
+            Some((_, val)) => val,
+            None => panic!("{}:{}: no {}", self.file, self.line, key),
+        };
+    }
+
+    fn header(&self, key: &str) -> &str {
+        return match self.headers.iter().find(|(k, _)| k == key) {
+            Some((_, val)) => val,
+            None => panic!("{}:{}: no [{}] header", self.file, self.line, key),
+        };
+    }
+
+    fn num(&self, val: &str) -> usize {
+        return match val.parse() {
+            Ok(num) => num,
+            Err(_) => panic!("{}:{}: {} isn't a number", self.file, self.line, val),
+        };
+    }
+
+    fn hex(&self, key: &str) -> Vec<u8> {
+        return match BitStream::from_hex(self.get(key)) {
+            Ok(bits) => bits.to_bytes(),
+            Err(err) => panic!("{}:{}: {}: {}", self.file, self.line, key, err),
+        };
+    }
+
+    /// the message, Len bits of Msg, which holds a single 00 byte when
+    /// Len is 0
+    fn msg(&self) -> Vec<u8> {
+        let len = self.num(self.get("Len"));
+        assert!(
+            len.is_multiple_of(8),
+            "{}:{}: bit-oriented messages aren't supported, use the byte-oriented files",
+            self.file,
+            self.line
+        );
+        let mut temp = self.hex("Msg");
+        temp.truncate(len / 8);
+        return temp;
+    }
+
+    /// the key that tells cases apart in messages, the first field
+    fn case(&self) -> String {
+        let (key, val) = &self.fields[0];
+        return format!("{} = {}", key, val);
+    }
+
+    fn check(&self, key: &str, actual: &[u8]) {
+        let expected = self.hex(key);
+        if actual != &expected[..] {
+            panic!(
+                "{}:{}: {} mismatched\n  expected {:x}\n  got      {:x}",
+                self.file,
+                self.line,
+                self.case(),
+                BitStream::from_bytes(&expected),
+                BitStream::from_bytes(actual),
+            );
+        }
+    }
+}
+
+/// vectors written by generate.py
+const GENERATED: &str = "generated";
+/// the official byte-oriented files from the CAVP site
+const CAVP: &str = "cavp";
+
+/// parses tests/vectors/dir/name, skipping # comments
+fn read(dir: &str, name: &str) -> Vec<Record> {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "vectors", dir, name]
+        .iter()
+        .collect();
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) => panic!("{}: {}", path.display(), err),
+    };
+    let mut records = Vec::new();
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut new_section = false;
+    let mut current: Option<Record> = None;
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            records.extend(current.take());
+            continue;
+        }
+        let (key, val) = match line.split_once('=') {
+            Some((key, val)) => (key.trim(), val.trim()),
+            None => (line, ""),
+        };
+        if let Some(key) = key.strip_prefix('[') {
+            // a header after records starts a new section
+            if new_section {
+                headers.clear();
+                new_section = false;
+            }
+            let (key, val) = match val.strip_suffix(']') {
+                Some(val) => (key, val),
+                None => (key.trim_end_matches(']'), ""),
+            };
+            headers.retain(|(k, _)| k != key);
+            headers.push((key.to_string(), val.to_string()));
+            continue;
+        }
+        new_section = true;
+        let record = current.get_or_insert_with(|| Record {
+            file: format!("{}/{}", dir, name),
+            line: idx + 1,
+            headers: headers.clone(),
+            fields: Vec::new(),
+        });
+        record.fields.push((key.to_string(), val.to_string()));
+    }
+    records.extend(current);
+    assert!(!records.is_empty(), "{}: no records", name);
+    return records;
+}
+
+fn sha3(bits: usize, input: &[u8]) -> Vec<u8> {
+    return match bits {
+        224 => sha3_224(input).to_vec(),
+        256 => sha3_256(input).to_vec(),
+        384 => sha3_384(input).to_vec(),
+        512 => sha3_512(input).to_vec(),
+        _ => unreachable!(),
+    };
+}
+
+fn shake(bits: usize, input: &[u8], len: usize) -> Vec<u8> {
+    let mut temp = vec![0; len];
+    match bits {
+        128 => shake128(input, &mut temp),
+        256 => shake256(input, &mut temp),
+        _ => unreachable!(),
+    }
+    return temp;
+}
+
+fn sha3_msg(dir: &str) {
+    for bits in [224, 256, 384, 512] {
+        for kind in ["ShortMsg", "LongMsg"] {
+            for record in read(dir, &format!("SHA3_{}{}.rsp", bits, kind)) {
+                assert_eq!(record.num(record.header("L")), bits);
+                record.check("MD", &sha3(bits, &record.msg()));
+            }
+        }
+    }
+}
+
+/// SHA3VS 6.2.3, each checkpoint hashes the last one 1000 times
+fn sha3_monte(dir: &str) {
+    for bits in [224, 256, 384, 512] {
+        let records = read(dir, &format!("SHA3_{}Monte.rsp", bits));
+        let mut md = records[0].hex("Seed");
+        for record in records.iter().filter(|record| record.has("COUNT")) {
+            for _ in 0..1000 {
+                md = sha3(bits, &md);
+            }
+            record.check("MD", &md);
+        }
+    }
+}
+
+fn shake_msg(dir: &str) {
+    for bits in [128, 256] {
+        for kind in ["ShortMsg", "LongMsg"] {
+            for record in read(dir, &format!("SHAKE{}{}.rsp", bits, kind)) {
+                let len = record.num(record.header("Outputlen")) / 8;
+                record.check("Output", &shake(bits, &record.msg(), len));
+            }
+        }
+    }
+}
+
+/// SHAKEVS 6.2.3, each message is the leftmost 128 bits of the last output
+/// and the next output length comes from its rightmost 16 bits
+fn shake_monte(dir: &str) {
+    for bits in [128, 256] {
+        let records = read(dir, &format!("SHAKE{}Monte.rsp", bits));
+        let first = &records[0];
+        let min = first.num(first.header("Minimum Output Length (bits)")) / 8;
+        let max = first.num(first.header("Maximum Output Length (bits)")) / 8;
+        let mut output = first.hex("Msg");
+        let mut len = max;
+        for record in records.iter().filter(|record| record.has("COUNT")) {
+            let mut last_len = len;
+            for _ in 0..1000 {
+                let mut msg = [0; 16];
+                let take = output.len().min(16);
+                msg[..take].copy_from_slice(&output[..take]);
+                output = shake(bits, &msg, len);
+                last_len = len;
+                let rightmost = u16::from_be_bytes([output[len - 2], output[len - 1]]);
+                len = min + rightmost as usize % (max - min + 1);
+            }
+            assert_eq!(record.num(record.get("Outputlen")), 8 * last_len);
+            record.check("Output", &output);
+        }
+    }
+}
+
+fn shake_variable_out(dir: &str) {
+    for bits in [128, 256] {
+        for record in read(dir, &format!("SHAKE{}VariableOut.rsp", bits)) {
+            let len = record.num(record.get("Outputlen"));
+            assert!(
+                len.is_multiple_of(8),
+                "{}:{}: bit lengths",
+                record.file,
+                record.line
+            );
+            record.check("Output", &shake(bits, &record.hex("Msg"), len / 8));
+        }
+    }
+}
+
+#[test]
+fn generated_sha3_msg() {
+    sha3_msg(GENERATED);
+}
+
+#[test]
+fn generated_sha3_monte() {
+    sha3_monte(GENERATED);
+}
+
+#[test]
+fn generated_shake_msg() {
+    shake_msg(GENERATED);
+}
+
+#[test]
+fn generated_shake_monte() {
+    shake_monte(GENERATED);
+}
+
+#[test]
+fn generated_shake_variable_out() {
+    shake_variable_out(GENERATED);
+}
+
+#[test]
+#[ignore = "needs the official files in tests/vectors/cavp, see the README there"]
+fn cavp_sha3_msg() {
+    sha3_msg(CAVP);
+}
+
+#[test]
+#[ignore = "needs the official files in tests/vectors/cavp, see the README there"]
+fn cavp_sha3_monte() {
+    sha3_monte(CAVP);
+}
+
+#[test]
+#[ignore = "needs the official files in tests/vectors/cavp, see the README there"]
+fn cavp_shake_msg() {
+    shake_msg(CAVP);
+}
+
+#[test]
+#[ignore = "needs the official files in tests/vectors/cavp, see the README there"]
+fn cavp_shake_monte() {
+    shake_monte(CAVP);
+}
+
+#[test]
+#[ignore = "needs the official files in tests/vectors/cavp, see the README there"]
+fn cavp_shake_variable_out() {
+    shake_variable_out(CAVP);
+}
+
+/// also absorbs the input in uneven pieces, which have to give the same
+/// output however they split the chunks
+#[test]
+fn k12_msg() {
+    for record in read(GENERATED, "K12.rsp") {
+        let len = record.num(record.header("Outputlen")) / 8;
+        let mut custom = record.hex("Customization");
+        custom.truncate(record.num(record.get("CustomizationLen")) / 8);
+        let msg = record.msg();
+        let mut temp = vec![0; len];
+        k12(&msg, &custom, &mut temp);
+        record.check("Output", &temp);
+
+        let mut k = KangarooTwelve::new();
+        for piece in msg.chunks(1000) {
+            let (first, rest) = piece.split_at(piece.len().min(7));
+            k.absorb(first);
+            k.absorb(rest);
+        }
+        k.finish(&custom);
+        for piece in temp.chunks_mut(100) {
+            k.squeeze(piece);
+        }
+        record.check("Output", &temp);
+    }
+}
+
+/// the KT128 examples from RFC 9861 section 5, independent of generate.py
+#[test]
+fn k12_rfc() {
+    let ptn = |len: usize| -> Vec<u8> { (0..len).map(|idx| (idx % 251) as u8).collect() };
+    let cases: [(Vec<u8>, Vec<u8>, &str); 9] = [
+        (
+            vec![],
+            vec![],
+            "1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5",
+        ),
+        (
+            ptn(1),
+            vec![],
+            "2bda92450e8b147f8a7cb629e784a058efca7cf7d8218e02d345dfaa65244a1f",
+        ),
+        (
+            ptn(17),
+            vec![],
+            "6bf75fa2239198db4772e36478f8e19b0f371205f6a9a93a273f51df37122888",
+        ),
+        (
+            ptn(17 * 17),
+            vec![],
+            "0c315ebcdedbf61426de7dcf8fb725d1e74675d7f5327a5067f367b108ecb67c",
+        ),
+        (
+            ptn(17usize.pow(3)),
+            vec![],
+            "cb552e2ec77d9910701d578b457ddf772c12e322e4ee7fe417f92c758f0d59d0",
+        ),
+        (
+            ptn(17usize.pow(4)),
+            vec![],
+            "8701045e22205345ff4dda05555cbb5c3af1a771c2b89baef37db43d9998b9fe",
+        ),
+        (
+            ptn(17usize.pow(5)),
+            vec![],
+            "844d610933b1b9963cbdeb5ae3b6b05cc7cbd67ceedf883eb678a0a8e0371682",
+        ),
+        (
+            vec![],
+            ptn(1),
+            "fab658db63e94a246188bf7af69a133045f46ee984c56e3c3328caaf1aa1a583",
+        ),
+        (
+            vec![0xFF],
+            ptn(41),
+            "d848c5068ced736f4462159b9867fd4c20b808acc3d5bc48e0b06ba0a3762ec4",
+        ),
+    ];
+    for (msg, custom, expected) in cases.iter() {
+        let mut temp = [0; 32];
+        k12(msg, custom, &mut temp);
+        assert_eq!(format!("{:x}", BitStream::from_bytes(&temp)), *expected);
+    }
+    // the last 32 of 10032 bytes of output for the empty message
+    let mut temp = vec![0; 10032];
+    k12(&[], &[], &mut temp);
+    assert_eq!(
+        format!("{:x}", BitStream::from_bytes(&temp[10000..])),
+        "e8dc563642f7228c84684c898405d3a834799158c079b12880277a1d28e2ff6d"
+    );
+}
+
+fn permute<L: Lane>(state: &[u8]) -> Vec<u8> {
+    let mut k = KeccakF::<L>::new();
+    k.xor_bytes(0, state);
+    k.permute();
+    let mut temp = vec![0; KeccakF::<L>::BYTES];
+    k.extract_bytes(0, &mut temp);
+    return temp;
+}
+
+#[test]
+fn keccak_f() {
+    let mut seen = Vec::new();
+    for record in read(GENERATED, "KeccakF.rsp") {
+        let width = record.num(record.header("Width"));
+        let state = record.hex("State");
+        let output = match width {
+            25 | 50 | 100 => continue,
+            200 => permute::<u8>(&state),
+            400 => permute::<u16>(&state),
+            800 => permute::<u32>(&state),
+            1600 => permute::<u64>(&state),
+            _ => panic!("{}:{}: width {}", record.file, record.line, width),
+        };
+        record.check("Output", &output);
+        seen.push(width);
+    }
+    seen.dedup();
+    assert_eq!(seen, [200, 400, 800, 1600]);
+}
+
+/// lanes of the zero state after one and after two permutations, from the
+/// Keccak team's KeccakF-{width}-IntermediateValues.txt as quoted by the
+/// tests of the RustCrypto keccak crate, so unlike everything in
+/// tests/vectors/generated they don't come from generate.py
+const XKCP_200: [[u64; 25]; 2] = [
+    [
+        0x3C, 0x28, 0x26, 0x84, 0x1C, 0xB3, 0x5C, 0x17, 0x1E, 0xAA, 0xE9, 0xB8, 0x11, 0x13, 0x4C,
+        0xEA, 0xA3, 0x85, 0x2C, 0x69, 0xD2, 0xC5, 0xAB, 0xAF, 0xEA,
+    ],
+    [
+        0x1B, 0xEF, 0x68, 0x94, 0x92, 0xA8, 0xA5, 0x43, 0xA5, 0x99, 0x9F, 0xDB, 0x83, 0x4E, 0x31,
+        0x66, 0xA1, 0x4B, 0xE8, 0x27, 0xD9, 0x50, 0x40, 0x47, 0x9E,
+    ],
+];
+
+const XKCP_400: [[u64; 25]; 2] = [
+    [
+        0x09F5, 0x40AC, 0x0FA9, 0x14F5, 0xE89F, 0xECA0, 0x5BD1, 0x7870, 0xEFF0, 0xBF8F, 0x0337,
+        0x6052, 0xDC75, 0x0EC9, 0xE776, 0x5246, 0x59A1, 0x5D81, 0x6D95, 0x6E14, 0x633E, 0x58EE,
+        0x71FF, 0x714C, 0xB38E,
+    ],
+    [
+        0xE537, 0xD5D6, 0xDBE7, 0xAAF3, 0x9BC7, 0xCA7D, 0x86B2, 0xFDEC, 0x692C, 0x4E5B, 0x67B1,
+        0x15AD, 0xA7F7, 0xA66F, 0x67FF, 0x3F8A, 0x2F99, 0xE2C2, 0x656B, 0x5F31, 0x5BA6, 0xCA29,
+        0xC224, 0xB85C, 0x097C,
+    ],
+];
+
+const XKCP_800: [[u64; 25]; 2] = [
+    [
+        0xE531D45D, 0xF404C6FB, 0x23A0BF99, 0xF1F8452F, 0x51FFD042, 0xE539F578, 0xF00B80A7,
+        0xAF973664, 0xBF5AF34C, 0x227A2424, 0x88172715, 0x9F685884, 0xB15CD054, 0x1BF4FC0E,
+        0x6166FA91, 0x1A9E599A, 0xA3970A1F, 0xAB659687, 0xAFAB8D68, 0xE74B1015, 0x34001A98,
+        0x4119EFF3, 0x930A0E76, 0x87B28070, 0x11EFE996,
+    ],
+    [
+        0x75BF2D0D, 0x9B610E89, 0xC826AF40, 0x64CD84AB, 0xF905BDD6, 0xBC832835, 0x5F8001B9,
+        0x15662CCE, 0x8E38C95E, 0x701FE543, 0x1B544380, 0x89ACDEFF, 0x51EDB5DE, 0x0E9702D9,
+        0x6C19AA16, 0xA2913EEE, 0x60754E9A, 0x9819063C, 0xF4709254, 0xD09F9084, 0x772DA259,
+        0x1DB35DF7, 0x5AA60162, 0x358825D5, 0xB3783BAB,
+    ],
+];
+
+const XKCP_1600: [[u64; 25]; 2] = [
+    [
+        0xF1258F7940E1DDE7,
+        0x84D5CCF933C0478A,
+        0xD598261EA65AA9EE,
+        0xBD1547306F80494D,
+        0x8B284E056253D057,
+        0xFF97A42D7F8E6FD4,
+        0x90FEE5A0A44647C4,
+        0x8C5BDA0CD6192E76,
+        0xAD30A6F71B19059C,
+        0x30935AB7D08FFC64,
+        0xEB5AA93F2317D635,
+        0xA9A6E6260D712103,
+        0x81A57C16DBCF555F,
+        0x43B831CD0347C826,
+        0x01F22F1A11A5569F,
+        0x05E5635A21D9AE61,
+        0x64BEFEF28CC970F2,
+        0x613670957BC46611,
+        0xB87C5A554FD00ECB,
+        0x8C3EE88A1CCF32C8,
+        0x940C7922AE3A2614,
+        0x1841F924A2C509E4,
+        0x16F53526E70465C2,
+        0x75F644E97F30A13B,
+        0xEAF1FF7B5CECA249,
+    ],
+    [
+        0x2D5C954DF96ECB3C,
+        0x6A332CD07057B56D,
+        0x093D8D1270D76B6C,
+        0x8A20D9B25569D094,
+        0x4F9C4F99E5E7F156,
+        0xF957B9A2DA65FB38,
+        0x85773DAE1275AF0D,
+        0xFAF4F247C3D810F7,
+        0x1F1B9EE6F79A8759,
+        0xE4FECC0FEE98B425,
+        0x68CE61B6B9CE68A1,
+        0xDEEA66C4BA8F974F,
+        0x33C43D836EAFB1F5,
+        0xE00654042719DBD9,
+        0x7CF8A9F009831265,
+        0xFD5449A6BF174743,
+        0x97DDAD33D8994B40,
+        0x48EAD5FC5D0BE774,
+        0xE3B8C8EE55B7B03C,
+        0x91A0226E649E42E9,
+        0x900E3129E7BADD7B,
+        0x202A9EC5FAA3CCE8,
+        0x5B3402464E1C3DB6,
+        0x609F4E62A44C1059,
+        0x20D06CD26A8FBF5C,
+    ],
+];
+
+#[test]
+fn keccak_f_xkcp() {
+    for (width, states) in [
+        (200, &XKCP_200),
+        (400, &XKCP_400),
+        (800, &XKCP_800),
+        (1600, &XKCP_1600),
+    ] {
+        let mut state = vec![0; width / 8];
+        let mut k = Keccak::zeroed(width);
+        for lanes in states.iter() {
+            let expected: Vec<u8> = lanes
+                .iter()
+                .flat_map(|lane| lane.to_le_bytes()[..width / 200].to_vec())
+                .collect();
+            state = match width {
+                200 => permute::<u8>(&state),
+                400 => permute::<u16>(&state),
+                800 => permute::<u32>(&state),
+                _ => permute::<u64>(&state),
+            };
+            assert_eq!(state, expected, "KeccakF, width {}", width);
+            k.keccak(12 + 2 * k.depth().trailing_zeros() as u64);
+            let actual = k.get_state().to_bytes_with_order(BitOrder::LsbFirst);
+            assert_eq!(actual, expected, "Keccak, width {}", width);
+        }
+    }
+}
+
+/// the bit-level Keccak for every width, including those KeccakF has no lane
+/// type for
+#[test]
+fn keccak_bit_level() {
+    for record in read(GENERATED, "KeccakF.rsp") {
+        let width = record.num(record.header("Width"));
+        let mut k = Keccak::from_bytes(&record.hex("State"), width).unwrap();
+        k.keccak(12 + 2 * k.depth().trailing_zeros() as u64);
+        let output = k.get_state().to_bytes_with_order(BitOrder::LsbFirst);
+        record.check("Output", &output);
+    }
+}
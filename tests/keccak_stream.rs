@@ -0,0 +1,75 @@
+//! runs the keccak_stream binary and checks its output against the library
+#![cfg(feature = "std")]
+
+use rand_keccak::{shake256, BitStream, KeccakCtr};
+use std::process::Command;
+
+fn stream(args: &[&str]) -> std::process::Output {
+    return Command::new(env!("CARGO_BIN_EXE_keccak_stream"))
+        .args(args)
+        .output()
+        .unwrap();
+}
+
+#[test]
+fn test_ctr_output() {
+    let out = stream(&["--seed", "5eed", "--bytes", "100", "--width", "200"]);
+    assert!(out.status.success());
+    let mut key = [0; 25];
+    shake256(&[0x5e, 0xed], &mut key);
+    let mut ctr = KeccakCtr::new(&BitStream::from_bytes(&key), 8, 18);
+    let expected: Vec<u8> = (0..13).flat_map(|_| ctr.next_u64().to_be_bytes()).collect();
+    assert_eq!(out.stdout, &expected[..100]);
+}
+
+#[test]
+fn test_extractions_differ() {
+    let ctr = stream(&["--seed", "5eed", "--bytes", "64"]).stdout;
+    let sequential = stream(&["--seed", "5eed", "--bytes", "64", "--extract", "sequential"]);
+    let sponge = stream(&["--seed", "5eed", "--bytes", "64", "--extract", "sponge"]);
+    assert_eq!(sequential.stdout.len(), 64);
+    assert_eq!(sponge.stdout.len(), 64);
+    assert_ne!(ctr, sequential.stdout);
+    assert_ne!(ctr, sponge.stdout);
+}
+
+#[test]
+fn test_seeds_differ() {
+    // zero bytes and bytes past the width all change the stream
+    let long = "5eed".repeat(201);
+    let seeds = ["", "00", "0000", &long[..400], &long[..402], &long];
+    for extract in ["ctr", "sequential", "sponge"] {
+        let outputs: Vec<Vec<u8>> = seeds
+            .iter()
+            .map(|seed| {
+                let out = stream(&["--seed", seed, "--bytes", "32", "--extract", extract]);
+                assert!(out.status.success(), "{} {:?}", extract, seed);
+                return out.stdout;
+            })
+            .collect();
+        for (idx, out) in outputs.iter().enumerate() {
+            assert!(
+                !outputs[..idx].contains(out),
+                "{} {:?}",
+                extract,
+                seeds[idx]
+            );
+        }
+    }
+}
+
+#[test]
+fn test_bad_arguments() {
+    for args in [
+        &["--width", "100"][..],
+        &["--rounds", "25"],
+        &["--rounds", "0"],
+        &["--seed", "5ee"],
+        &["--extract", "xor"],
+        &["--bytes"],
+    ] {
+        let out = stream(args);
+        assert_eq!(out.status.code(), Some(2), "{:?}", args);
+        assert!(out.stdout.is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+//! runs the keccaksum binary on files and stdin
+#![cfg(feature = "std")]
+
+use std::{
+    env, fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+fn keccaksum(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_keccaksum"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    return child.wait_with_output().unwrap();
+}
+
+/// a file in the temp directory unique to this test process
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = env::temp_dir().join(format!("keccaksum-{}-{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    return path;
+}
+
+#[test]
+fn test_stdin() {
+    let cases = [
+        (
+            "sha3-256",
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+        ),
+        (
+            "keccak-256",
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+        ),
+        (
+            "shake128",
+            "5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc8",
+        ),
+    ];
+    for (algorithm, hash) in cases {
+        let out = keccaksum(&["-a", algorithm], b"abc");
+        assert!(out.status.success());
+        assert_eq!(
+            String::from_utf8(out.stdout).unwrap(),
+            format!("{}  -\n", hash)
+        );
+    }
+    let out = keccaksum(&["-a", "k12", "-l", "64"], b"");
+    assert_eq!(out.stdout, b"1ac2d450fc3b4205  -\n");
+}
+
+#[test]
+fn test_check() {
+    let data = temp_file("data", &[0x5e; 20000]);
+    let data = data.to_str().unwrap();
+    let sums = keccaksum(&["-a", "k12", "-l", "512", data], b"");
+    assert!(sums.status.success());
+    let good = temp_file("good", &sums.stdout);
+    let out = keccaksum(&["-a", "k12", "-c", good.to_str().unwrap()], b"");
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        format!("{}: OK\n", data)
+    );
+
+    // a changed checksum, a line that isn't one and a missing file
+    let mut bad = sums.stdout.clone();
+    bad[0] ^= 1;
+    bad.extend_from_slice(b"not a checksum\n");
+    let out = keccaksum(&["-a", "k12", "-c", "-"], &bad);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        format!("{}: FAILED\n", data)
+    );
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(
+        stderr.contains("1 lines are improperly formatted"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("1 computed checksums did NOT match"),
+        "{}",
+        stderr
+    );
+
+    // improperly formatted lines alone only fail with --strict
+    let mut improper = sums.stdout.clone();
+    improper.extend_from_slice(b"not a checksum\n");
+    let out = keccaksum(&["-a", "k12", "-c"], &improper);
+    assert!(out.status.success());
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(
+        stderr.contains("1 lines are improperly formatted"),
+        "{}",
+        stderr
+    );
+    let out = keccaksum(&["-a", "k12", "-c", "--strict"], &improper);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        format!("{}: OK\n", data)
+    );
+
+    // lists written on Windows, with blank lines between checksums
+    let crlf = String::from_utf8(sums.stdout.clone())
+        .unwrap()
+        .replace('\n', "\r\n\r\n");
+    let out = keccaksum(&["-a", "k12", "-c"], crlf.as_bytes());
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        format!("{}: OK\n", data)
+    );
+
+    // nothing to check is a failure, not a silent success
+    for list in [&b""[..], b"\n\n", b"\r\n"] {
+        let out = keccaksum(&["-a", "k12", "-c"], list);
+        assert_eq!(out.status.code(), Some(1), "{:?}", list);
+        let stderr = String::from_utf8(out.stderr).unwrap();
+        assert!(
+            stderr.contains("-: no properly formatted checksum lines found"),
+            "{}",
+            stderr
+        );
+    }
+
+    let missing = keccaksum(&["no such file"], b"");
+    assert_eq!(missing.status.code(), Some(1));
+    for path in [data, good.to_str().unwrap()] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn test_bad_arguments() {
+    for args in [
+        &["-a", "md5"][..],
+        &["-a", "sha3-256", "-l", "128"],
+        &["-a", "shake128", "-l", "12"],
+        &["--frobnicate"],
+        &["--strict"],
+    ] {
+        assert_eq!(keccaksum(args, b"").status.code(), Some(2), "{:?}", args);
+    }
+}
+
+#[test]
+fn test_closed_stdout() {
+    let data = temp_file("small", b"abc");
+    let data = data.to_str().unwrap();
+    let line = format!("{}  {}\n", "0".repeat(64), data);
+    let list = temp_file("long-list", line.repeat(5000).as_bytes());
+    for args in [vec!["-c", list.to_str().unwrap()], vec![data; 5000]] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_keccaksum"))
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // like `keccaksum ... | head -0`, the output is more than a pipe holds
+        drop(child.stdout.take());
+        let out = child.wait_with_output().unwrap();
+        assert_eq!(out.status.code(), Some(0));
+        assert_eq!(String::from_utf8(out.stderr).unwrap(), "");
+    }
+    for path in [data, list.to_str().unwrap()] {
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,47 @@
+//! counts live heap bytes with a wrapping global allocator, to check that
+//! constructing and dropping generators gives all their memory back.
+#![cfg(feature = "alloc")]
+
+use rand_keccak::{BitStream, Keccak};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(layout.size(), Ordering::SeqCst);
+        return System.alloc(layout);
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(layout.size(), Ordering::SeqCst);
+        return System.alloc_zeroed(layout);
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+#[test]
+fn test_no_leak() {
+    let seed = BitStream::from_u64(&[0xdeadbeef]);
+    let before = LIVE.load(Ordering::SeqCst);
+    for _ in 0..1_000_000 {
+        let k = Keccak::new_sized(&seed, 1);
+        let copy = k.clone();
+        drop(k);
+        drop(copy);
+    }
+    let mut k = Keccak::new_sized(&seed, 8);
+    k.keccak(18);
+    drop(k);
+    assert_eq!(LIVE.load(Ordering::SeqCst), before);
+}
@@ -0,0 +1,49 @@
+//! builds the library without its default features, for the host and, when
+//! its standard library is installed, for the bare metal thumbv7em-none-eabi
+//! target, which has no std at all.
+
+use std::{path::Path, process::Command};
+
+const TARGET: &str = "thumbv7em-none-eabi";
+
+fn cargo_check(args: &[&str]) {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let status = Command::new(env!("CARGO"))
+        .args(["check", "--lib", "--quiet", "--no-default-features"])
+        .arg("--manifest-path")
+        .arg(Path::new(manifest_dir).join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(Path::new(manifest_dir).join("target").join("no_std"))
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success(), "cargo check {:?} failed", args);
+}
+
+fn target_installed(target: &str) -> bool {
+    let output = match Command::new("rustc").args(["--print", "sysroot"]).output() {
+        Ok(output) => output,
+        Err(_) => return false,
+    };
+    let sysroot = String::from_utf8_lossy(&output.stdout);
+    return Path::new(sysroot.trim())
+        .join("lib/rustlib")
+        .join(target)
+        .exists();
+}
+
+#[test]
+fn test_no_std_host() {
+    cargo_check(&[]);
+    cargo_check(&["--features", "alloc"]);
+}
+
+#[test]
+fn test_no_std_bare_metal() {
+    if !target_installed(TARGET) {
+        eprintln!("{} isn't installed, skipping", TARGET);
+        return;
+    }
+    cargo_check(&["--target", TARGET]);
+    cargo_check(&["--target", TARGET, "--features", "alloc"]);
+}
@@ -0,0 +1,356 @@
+#!/usr/bin/env python3
+"""Writes the .rsp and intermediate values files in this directory, and
+e.hex in tests/vectors, see README.md there.
+
+The hash and XOF vectors come from hashlib and follow the CAVP SHA3VS and
+SHAKEVS procedures, the permutation vectors and traces come from the Keccak-p
+reference below, which is checked against hashlib before anything is written.
+"""
+
+import hashlib
+import os
+import random
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+VECTORS = os.path.dirname(HERE)
+
+RATES = {224: 144, 256: 136, 384: 104, 512: 72, 128: 168}
+
+
+# Keccak-p[25 * w] straight from FIPS 202 section 3.2, lane (x, y) is
+# lanes[x + 5 * y] holding bit z of the lane in bit z of an integer
+
+
+def keccak_p(lanes, w, rounds, trace=None):
+    """the last rounds rounds of Keccak-p[25 * w], calling trace(round, step,
+    lanes) after each step mapping"""
+    l = w.bit_length() - 1
+    mask = (1 << w) - 1
+
+    def rot(val, n):
+        n %= w
+        return ((val << n) | (val >> (w - n))) & mask
+
+    def rc(t):
+        if t % 255 == 0:
+            return 1
+        r = 1
+        for _ in range(t % 255):
+            r <<= 1
+            if r & 0x100:
+                r ^= 0x171
+        return r & 1
+
+    def theta(a):
+        c = [a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20] for x in range(5)]
+        d = [c[(x - 1) % 5] ^ rot(c[(x + 1) % 5], 1) for x in range(5)]
+        return [a[i] ^ d[i % 5] for i in range(25)]
+
+    def rho(a):
+        b = list(a)
+        x, y = 1, 0
+        for t in range(24):
+            b[x + 5 * y] = rot(a[x + 5 * y], (t + 1) * (t + 2) // 2)
+            x, y = y, (2 * x + 3 * y) % 5
+        return b
+
+    def pi(a):
+        return [a[(x + 3 * y) % 5 + 5 * x] for y in range(5) for x in range(5)]
+
+    def chi(a):
+        return [
+            a[x + 5 * y] ^ (~a[(x + 1) % 5 + 5 * y] & a[(x + 2) % 5 + 5 * y]) & mask
+            for y in range(5)
+            for x in range(5)
+        ]
+
+    def iota(a, ir):
+        a = list(a)
+        for j in range(l + 1):
+            a[0] ^= rc(j + 7 * ir) << ((1 << j) - 1)
+        return a
+
+    a = list(lanes)
+    for ir in range(12 + 2 * l - rounds, 12 + 2 * l):
+        for name, step in [("theta", theta), ("rho", rho), ("pi", pi), ("chi", chi),
+                           ("iota", lambda a: iota(a, ir))]:
+            a = step(a)
+            if trace:
+                trace(ir, name, a)
+    return a
+
+
+def to_lanes(state, w):
+    bits = int.from_bytes(state, "little")
+    return [(bits >> (w * i)) & ((1 << w) - 1) for i in range(25)]
+
+
+def from_lanes(lanes, w):
+    bits = 0
+    for i, lane in enumerate(lanes):
+        bits |= lane << (w * i)
+    return bits.to_bytes((25 * w + 7) // 8, "little")
+
+
+def sponge(msg, rate, suffix, out_len, rounds=24):
+    state = [0] * 25
+    padded = bytearray(msg)
+    padded.append(suffix)
+    padded.extend(b"\0" * (-len(padded) % rate))
+    padded[-1] |= 0x80
+    for start in range(0, len(padded), rate):
+        block = to_lanes(padded[start:start + rate] + bytes(200 - rate), 64)
+        state = keccak_p([s ^ b for s, b in zip(state, block)], 64, rounds)
+    out = b""
+    while len(out) < out_len:
+        out += from_lanes(state, 64)[:rate]
+        state = keccak_p(state, 64, rounds)
+    return out[:out_len]
+
+
+def length_encode(x):
+    encoded = x.to_bytes((x.bit_length() + 7) // 8, "big")
+    return encoded + bytes([len(encoded)])
+
+
+def k12(msg, custom, out_len):
+    """KangarooTwelve as in RFC 9861, TurboSHAKE128 is the sponge over 12
+    rounds with rate 168"""
+    s = msg + custom + length_encode(len(custom))
+    if len(s) <= 8192:
+        return sponge(s, 168, 0x07, out_len, 12)
+    chunks = [s[start:start + 8192] for start in range(0, len(s), 8192)]
+    node = chunks[0] + b"\x03" + bytes(7)
+    for chunk in chunks[1:]:
+        node += sponge(chunk, 168, 0x0B, 32, 12)
+    node += length_encode(len(chunks) - 1) + b"\xff\xff"
+    return sponge(node, 168, 0x06, out_len, 12)
+
+
+def ptn(n):
+    """the RFC 9861 test pattern"""
+    return bytes(i % 251 for i in range(n))
+
+
+def self_check():
+    rng = random.Random(202)
+    for n in [0, 1, 71, 72, 135, 136, 167, 168, 300]:
+        msg = bytes(rng.randrange(256) for _ in range(n))
+        for bits, rate in [(224, 144), (256, 136), (384, 104), (512, 72)]:
+            expected = hashlib.new("sha3_%d" % bits, msg).digest()
+            assert sponge(msg, rate, 0x06, bits // 8) == expected
+        assert sponge(msg, 168, 0x1F, 500) == hashlib.shake_128(msg).digest(500)
+        assert sponge(msg, 136, 0x1F, 500) == hashlib.shake_256(msg).digest(500)
+    # the first lane of Keccak-f[1600] applied to the zero state, as given
+    # in the Keccak team's KeccakF-1600-IntermediateValues.txt
+    assert keccak_p([0] * 25, 64, 24)[0] == 0xF1258F7940E1DDE7
+    # KT128 test vectors from RFC 9861 section 5
+    rfc = [
+        (b"", b"", "1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5"),
+        (ptn(17), b"", "6bf75fa2239198db4772e36478f8e19b0f371205f6a9a93a273f51df37122888"),
+        (ptn(17 ** 3), b"", "cb552e2ec77d9910701d578b457ddf772c12e322e4ee7fe417f92c758f0d59d0"),
+        (ptn(17 ** 4), b"", "8701045e22205345ff4dda05555cbb5c3af1a771c2b89baef37db43d9998b9fe"),
+        (b"", ptn(1), "fab658db63e94a246188bf7af69a133045f46ee984c56e3c3328caaf1aa1a583"),
+        (b"\xff", ptn(41), "d848c5068ced736f4462159b9867fd4c20b808acc3d5bc48e0b06ba0a3762ec4"),
+    ]
+    for msg, custom, expected in rfc:
+        assert k12(msg, custom, 32).hex() == expected
+
+
+def header(f, name):
+    f.write("#  %s\n" % name)
+    f.write("#  Generated by generate.py, see README.md\n")
+    f.write("#  Length values represented in bits\n\n")
+
+
+def hexs(data):
+    return data.hex() if data else "00"
+
+
+def message_file(path, name, section, digest, lengths, rng):
+    with open(path, "w") as f:
+        header(f, name)
+        f.write("[%s]\n\n" % section)
+        for n in lengths:
+            msg = bytes(rng.randrange(256) for _ in range(n))
+            out_name, out = digest(msg)
+            f.write("Len = %d\nMsg = %s\n%s = %s\n\n" % (8 * n, hexs(msg), out_name, out.hex()))
+
+
+def long_lengths(rate):
+    return [(i + 1) * rate + (37 * i) % rate + 1 for i in range(20)]
+
+
+def sha3(bits, rng):
+    fn = "sha3_%d" % bits
+    rate = RATES[bits]
+    size = bits // 8
+    digest = lambda msg: ("MD", hashlib.new(fn, msg).digest())
+    base = os.path.join(HERE, "SHA3_%d" % bits)
+    message_file(base + "ShortMsg.rsp", "SHA3-%d ShortMsg" % bits, "L = %d" % bits,
+                 digest, range(rate + 1), rng)
+    message_file(base + "LongMsg.rsp", "SHA3-%d LongMsg" % bits, "L = %d" % bits,
+                 digest, long_lengths(rate), rng)
+    # SHA3VS 6.2.3: MD_i = H(MD_i-1), 1000 times per checkpoint
+    with open(base + "Monte.rsp", "w") as f:
+        header(f, "SHA3-%d Monte" % bits)
+        seed = bytes(rng.randrange(256) for _ in range(size))
+        f.write("[L = %d]\n\nSeed = %s\n\n" % (bits, seed.hex()))
+        md = seed
+        for count in range(100):
+            for _ in range(1000):
+                md = hashlib.new(fn, md).digest()
+            f.write("COUNT = %d\nMD = %s\n\n" % (count, md.hex()))
+
+
+def shake(bits, rng):
+    fn = "shake_%d" % bits
+    rate = RATES[bits]
+    base = os.path.join(HERE, "SHAKE%d" % bits)
+    out_bits = bits
+    digest = lambda msg: ("Output", hashlib.new(fn, msg).digest(out_bits // 8))
+    section = "Outputlen = %d" % out_bits
+    message_file(base + "ShortMsg.rsp", "SHAKE%d ShortMsg" % bits, section,
+                 digest, range(rate + 1), rng)
+    message_file(base + "LongMsg.rsp", "SHAKE%d LongMsg" % bits, section,
+                 digest, long_lengths(rate), rng)
+
+    min_len, max_len = 16, 140
+    limits = "[Minimum Output Length (bits) = %d]\n[Maximum Output Length (bits) = %d]\n"
+    # SHAKEVS 6.2.3: the message is the leftmost 128 bits of the last
+    # output, the next output length comes from its rightmost 16 bits
+    with open(base + "Monte.rsp", "w") as f:
+        header(f, "SHAKE%d Monte" % bits)
+        f.write(limits % (8 * min_len, 8 * max_len))
+        seed = bytes(rng.randrange(256) for _ in range(16))
+        f.write("\nMsg = %s\n\n" % seed.hex())
+        out = seed
+        out_len = max_len
+        for count in range(100):
+            for _ in range(1000):
+                msg = out[:16].ljust(16, b"\0")
+                out = hashlib.new(fn, msg).digest(out_len)
+                last_len = out_len
+                rightmost = int.from_bytes(out[-2:], "big")
+                out_len = min_len + rightmost % (max_len - min_len + 1)
+            f.write("COUNT = %d\nOutputlen = %d\nOutput = %s\n\n" % (count, 8 * last_len, out.hex()))
+
+    with open(base + "VariableOut.rsp", "w") as f:
+        header(f, "SHAKE%d VariableOut" % bits)
+        f.write("[Tested for Output of byte-oriented messages]\n[Input Length = %d]\n" % bits)
+        f.write(limits % (8 * min_len, 8 * max_len))
+        f.write("\n")
+        for count in range(max_len - min_len + 1):
+            out_len = min_len + count
+            msg = bytes(rng.randrange(256) for _ in range(bits // 8))
+            out = hashlib.new(fn, msg).digest(out_len)
+            f.write("COUNT = %d\nOutputlen = %d\nMsg = %s\nOutput = %s\n\n"
+                    % (count, 8 * out_len, msg.hex(), out.hex()))
+
+
+def permutations(rng):
+    with open(os.path.join(HERE, "KeccakF.rsp"), "w") as f:
+        header(f, "Keccak-f[b] permutation, b = 25 * w")
+        f.write("#  State and Output are the state before and after one full\n")
+        f.write("#  permutation, bit i of the state is bit i % 8 of byte i / 8\n\n")
+        for w in [1, 2, 4, 8, 16, 32, 64]:
+            size = (25 * w + 7) // 8
+            states = [bytes(size)]
+            states += [(1 << bit).to_bytes(size, "little") for bit in [0, 25 * w - 1]]
+            for _ in range(5):
+                bits = rng.getrandbits(25 * w)
+                states.append(bits.to_bytes(size, "little"))
+            f.write("[Width = %d]\n\n" % (25 * w))
+            for count, state in enumerate(states):
+                out = from_lanes(keccak_p(to_lanes(state, w), w, 12 + 2 * (w.bit_length() - 1)), w)
+                f.write("COUNT = %d\nState = %s\nOutput = %s\n\n" % (count, state.hex(), out.hex()))
+
+
+def kangaroo_twelve(rng):
+    """messages and customization strings either side of the chunk
+    boundaries, where the input with its customization and length encoding
+    just fits or just doesn't fit in one or more chunks"""
+    cases = [(0, 0), (1, 0), (0, 1), (17, 41), (167, 0), (168, 0), (8191, 0), (8190, 1),
+             (8192, 0), (8189, 1), (8193, 0), (16384, 0), (2 * 8192 + 17, 5), (0, 8192)]
+    with open(os.path.join(HERE, "K12.rsp"), "w") as f:
+        header(f, "KangarooTwelve (KT128, RFC 9861)")
+        f.write("#  Customization holds a single 00 byte when CustomizationLen is 0\n\n")
+        for out_bits, sizes in [(256, cases), (8 * 1000, cases[:4] + cases[10:11])]:
+            f.write("[Outputlen = %d]\n\n" % out_bits)
+            for count, (msg_len, custom_len) in enumerate(sizes):
+                msg = bytes(rng.randrange(256) for _ in range(msg_len))
+                custom = bytes(rng.randrange(256) for _ in range(custom_len))
+                out = k12(msg, custom, out_bits // 8)
+                f.write("COUNT = %d\nLen = %d\nMsg = %s\nCustomizationLen = %d\n"
+                        "Customization = %s\nOutput = %s\n\n"
+                        % (count, 8 * msg_len, hexs(msg), 8 * custom_len, hexs(custom),
+                           out.hex()))
+
+
+def hex_rows(f, state):
+    for start in range(0, len(state), 16):
+        f.write(" ".join("%02X" % byte for byte in state[start:start + 16]) + "\n")
+
+
+def intermediate_values():
+    """traces laid out like the Keccak team's KeccakF-*-IntermediateValues.txt,
+    two permutations starting from the zero state"""
+    for w in [1, 2, 4, 8, 16, 32, 64]:
+        b = 25 * w
+        rounds = 12 + 2 * (w.bit_length() - 1)
+        with open(os.path.join(HERE, "KeccakF-%d-IntermediateValues.txt" % b), "w") as f:
+            f.write("+++ Keccak-f[%d] +++\n\n" % b)
+            f.write("Generated by generate.py, see README.md. States are bytes, bit i\n")
+            f.write("of the state is bit i % 8 of byte i / 8.\n\n")
+            state = bytes((b + 7) // 8)
+
+            def trace(ir, name, a):
+                if name == "theta":
+                    f.write("--- Round %d ---\n\n" % ir)
+                f.write("After %s:\n" % name)
+                hex_rows(f, from_lanes(a, w))
+                f.write("\n")
+
+            for _ in range(2):
+                f.write("Input of permutation:\n")
+                hex_rows(f, state)
+                f.write("\n")
+                state = from_lanes(keccak_p(to_lanes(state, w), w, rounds, trace), w)
+                f.write("State after permutation:\n")
+                hex_rows(f, state)
+                f.write("\n")
+
+
+def e_bits():
+    """the first 100,000 bits of e in binary, the integer part 10 first as in
+    NIST's data.e, as hex"""
+    n = 100000
+    # e = sum of 1 / k!, in fixed point with 64 guard bits
+    one = 1 << (n + 64)
+    total, term, k = 0, one, 0
+    while term:
+        total += term
+        k += 1
+        term //= k
+    bits = bin(total >> 64)[2:][:n]
+    with open(os.path.join(VECTORS, "e.hex"), "w") as f:
+        digits = "%0*x" % (n // 4, int(bits, 2))
+        for start in range(0, len(digits), 64):
+            f.write(digits[start:start + 64] + "\n")
+
+
+def main():
+    self_check()
+    rng = random.Random(202)
+    for bits in [224, 256, 384, 512]:
+        sha3(bits, rng)
+    for bits in [128, 256]:
+        shake(bits, rng)
+    permutations(rng)
+    kangaroo_twelve(rng)
+    intermediate_values()
+    e_bits()
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,35 @@
+#![cfg(feature = "alloc")]
+
+use proptest::prelude::*;
+use rand_keccak::BitStream;
+
+mod common;
+
+proptest! {
+    #[test]
+    fn slice_matches_get(
+        bytes in common::bytes(0..40),
+        a in any::<usize>(),
+        b in any::<usize>(),
+    ) {
+        let bits = BitStream::from_bytes(&bytes);
+        let (start, end) = if bits.is_empty() {
+            (0, 0)
+        } else {
+            let (a, b) = (a % (bits.len() + 1), b % (bits.len() + 1));
+            (a.min(b), a.max(b))
+        };
+        let view = bits.slice(start..end);
+        let owned = view.to_bit_stream();
+        prop_assert_eq!(owned.len(), end - start);
+        for idx in 0..owned.len() {
+            prop_assert_eq!(owned.get(idx), bits.get(start + idx));
+            prop_assert_eq!(view.get(idx), bits.get(start + idx));
+        }
+        prop_assert_eq!(view.count_ones(), owned.count_ones());
+        let mut rotated = BitStream::from_bytes(&bytes);
+        rotated.rotate_left(start);
+        rotated.rotate_right(start);
+        prop_assert_eq!(rotated, bits);
+    }
+}
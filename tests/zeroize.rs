@@ -0,0 +1,78 @@
+//! checks secret state is zeroed before it's freed, using a global allocator
+//! that inspects every block of a watched size as it is deallocated.
+#![cfg(feature = "alloc")]
+
+use rand_keccak::{BitStream, Keccak, KeccakCtr};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Inspecting;
+
+/// size of the allocations to inspect, 0 to inspect none
+static WATCH_SIZE: AtomicUsize = AtomicUsize::new(0);
+static FREED: AtomicUsize = AtomicUsize::new(0);
+static DIRTY: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Inspecting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        return System.alloc(layout);
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if layout.size() == WATCH_SIZE.load(Ordering::SeqCst) {
+            FREED.fetch_add(1, Ordering::SeqCst);
+            let bytes = std::slice::from_raw_parts(ptr, layout.size());
+            if bytes.iter().any(|&byte| byte != 0) {
+                DIRTY.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Inspecting = Inspecting;
+
+/// runs f with deallocations of size bytes inspected, returns how many were
+/// freed and how many of those still held non-zero bytes
+fn inspect<F: FnOnce()>(size: usize, f: F) -> (usize, usize) {
+    FREED.store(0, Ordering::SeqCst);
+    DIRTY.store(0, Ordering::SeqCst);
+    WATCH_SIZE.store(size, Ordering::SeqCst);
+    f();
+    WATCH_SIZE.store(0, Ordering::SeqCst);
+    return (FREED.load(Ordering::SeqCst), DIRTY.load(Ordering::SeqCst));
+}
+
+// the watched sizes are unusual enough that nothing else in the test binary
+// allocates them, all checks share one test so they can't run concurrently
+// and this binary has no other tests
+#[test]
+fn test_wiped_before_free() {
+    let secret = [0x5ec2e7_u64; 13];
+
+    // a keccak state of depth 32 is two 800 byte VLAs
+    let k = Keccak::new_sized(&BitStream::from_u64(&secret), 32);
+    let (freed, dirty) = inspect(800, || {
+        let mut k = k;
+        k.keccak(10);
+        drop(k);
+    });
+    assert_eq!((freed, dirty), (2, 0));
+
+    // 13 u64 words of bits
+    let bits = BitStream::from_u64(&secret);
+    let (freed, dirty) = inspect(13 * 8, move || drop(bits));
+    assert_eq!((freed, dirty), (1, 0));
+
+    // a depth 64 KeccakCtr buffers 12 u64 of output
+    let mut ctr = KeccakCtr::new(&BitStream::from_u64(&secret), 64, 12);
+    ctr.next_u64();
+    let (freed, dirty) = inspect(12 * 8, move || drop(ctr));
+    assert_eq!((freed, dirty), (1, 0));
+
+    // sanity check that the allocator does see secrets left behind
+    let leftover = vec![0x5ec2e7_u64; 13];
+    let (freed, dirty) = inspect(13 * 8, move || drop(leftover));
+    assert_eq!((freed, dirty), (1, 1));
+}